@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_mock::{Application, IdentityCalculator};
+
+fuzz_target!(|data: &[u8]| {
+  if let Ok(script) = std::str::from_utf8(data) {
+    let app = Application::new(Box::new(IdentityCalculator));
+    // any result is fine, as long as it's a result rather than a panic
+    let _ = app.run_script(script);
+  }
+});
@@ -13,14 +13,15 @@
 //! [`Application`](struct.Application.html) that is [`I32Calculator`](trait.I32Calculator.html).
 //!
 //! To enable mocking of [`I32Calculator`](trait.I32Calculator.html), we add the
-//! `#[cfg_attr(test, mockall::automock)]` attribute to the trait's definition.
+//! `#[cfg_attr(test, mockall::automock)]` attribute to the trait's definition. The crate's tests
+//! need `std` because the mocks do, so here it is `cfg_attr(all(test, feature = "std"), ..)`.
 //!
 //! In our unit test, we then create a mock object that is
 //! [`I32Calculator`](trait.I32Calculator.html) and set up expectations and return values for the
 //! methods will be called during the
 //! [`cool_algorithm`](struct.Application.html#method.cool_algorithm) call.
 //!
-//! ```rust
+//! ```ignore
 //! #[cfg(test)]
 //! mod tests {
 //!   use super::*;
@@ -59,19 +60,44 @@
 //!       .return_const(number);
 //!
 //!     // create our application with our mock calculator
-//!     let app = Application {
-//!       i32_calculator: Box::new(mock_i32_calculator),
-//!     };
+//!     let app = Application::new(Box::new(mock_i32_calculator));
 //!
 //!     // run our unit test of the cool_algorithm
 //!     assert_eq!(app.cool_algorithm(number), number);
 //!   }
 //! }
 //! ```
+//!
+//! The calculators and [`Operation`](enum.Operation.html)s only need `core`, so the crate builds with
+//! `#![no_std]` when its default `std` feature is disabled. APIs that allocate, including
+//! [`Application`](struct.Application.html), additionally need the `alloc` feature.
+//!
+//! The crate also builds for `wasm32-unknown-unknown` with any features, but there the `proptest`
+//! feature does nothing, and calculators that block the thread or catch panics are unavailable.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
+#[cfg(feature = "async")]
+use futures::future::BoxFuture;
+
+/// The clock used by [`DeadlineCalculator`](struct.DeadlineCalculator.html) and
+/// [`TimingCalculator`](struct.TimingCalculator.html).
+///
+/// This is `std::time::Instant`, except on `wasm32` targets, where `std`'s clock panics and
+/// `web_time::Instant` is used instead.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use std::time::Instant;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub use web_time::Instant;
 
 /// Mockable trait that a client for an external service would implement.
-#[cfg_attr(test, mockall::automock)]
-pub trait I32Calculator {
+#[cfg_attr(all(test, feature = "std"), mockall::automock)]
+pub trait I32Calculator: AsAny {
   /// Returns the sum of `x` and `y`.
   fn add(&self, x: i32, y: i32) -> i32;
   /// Returns the difference of `x` and `y`.
@@ -80,13 +106,359 @@ pub trait I32Calculator {
   fn multiply(&self, x: i32, y: i32) -> i32;
   /// Returns the quotient of `x` and `y`.
   fn divide(&self, x: i32, y: i32) -> i32;
+
+  /// Returns whether this calculator can perform operations of kind `op_kind`, so callers can route
+  /// unsupported ones elsewhere.
+  ///
+  /// By default every kind is supported.
+  fn supports(&self, _op_kind: OpKind) -> bool {
+    true
+  }
+
+  /// Returns a human-readable name of the backend, for diagnostics and logging.
+  ///
+  /// By default this is `"unknown"`.
+  fn backend_name(&self) -> &'static str {
+    "unknown"
+  }
+
+  /// Returns a cheap estimate of the result of `op` without calling the backend, e.g. for query
+  /// planning, or `None` if there isn't one.
+  ///
+  /// By default this is the exact result from
+  /// [`fold_constant`](enum.Operation.html#method.fold_constant), which is `None` when the
+  /// operation overflows or divides by zero and so depends on how the backend handles that.
+  fn estimate(&self, op: &Operation) -> Option<i32> {
+    op.fold_constant()
+  }
+
+  /// Returns a copy of this calculator behind a new box, or `None` if it can't be copied, which
+  /// lets [`Application::try_clone`](struct.Application.html#method.try_clone) and `Application`'s
+  /// `Clone` impl copy applications whose calculator type isn't known.
+  ///
+  /// Calculators that are `Clone` can implement this as `Some(Box::new(self.clone()))`. By default
+  /// this returns `None`.
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    None
+  }
+
+  /// Returns an error if `x` and `y` aren't acceptable operands, e.g. because the service doesn't
+  /// support them.
+  ///
+  /// The checked methods call this before computing anything. By default every operand is
+  /// accepted.
+  fn validate(&self, _x: i32, _y: i32) -> Result<(), CalculatorError> {
+    Ok(())
+  }
+
+  /// Returns the sum of `x` and `y`, or an error if it doesn't fit in `i32`.
+  ///
+  /// By default this checks for overflow before calling [`add`](#tymethod.add).
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.validate(x, y)?;
+    x.checked_add(y)
+      .ok_or(CalculatorError::Overflow { op: "add", x, y })?;
+    Ok(self.add(x, y))
+  }
+
+  /// Returns the difference of `x` and `y`, or an error if it doesn't fit in `i32`.
+  ///
+  /// By default this checks for overflow before calling [`subtract`](#tymethod.subtract).
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.validate(x, y)?;
+    x.checked_sub(y).ok_or(CalculatorError::Overflow {
+      op: "subtract",
+      x,
+      y,
+    })?;
+    Ok(self.subtract(x, y))
+  }
+
+  /// Returns the product of `x` and `y`, or an error if it doesn't fit in `i32`.
+  ///
+  /// By default this checks for overflow before calling [`multiply`](#tymethod.multiply).
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.validate(x, y)?;
+    x.checked_mul(y).ok_or(CalculatorError::Overflow {
+      op: "multiply",
+      x,
+      y,
+    })?;
+    Ok(self.multiply(x, y))
+  }
+
+  /// Returns the quotient of `x` and `y`, or an error if `y` is zero or the quotient doesn't fit in
+  /// `i32`.
+  ///
+  /// By default this checks for both before calling [`divide`](#tymethod.divide).
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.validate(x, y)?;
+    if y == 0 {
+      return Err(CalculatorError::DivisionByZero { dividend: x });
+    }
+    x.checked_div(y)
+      .ok_or(CalculatorError::Overflow { op: "divide", x, y })?;
+    Ok(self.divide(x, y))
+  }
+
+  /// Returns the remainder of dividing `x` by `y`, with the sign of `x` like `%`, or an error if `y`
+  /// is zero or the division overflows (`i32::MIN % -1`).
+  ///
+  /// By default this checks for both before computing `x - (x / y) * y` with
+  /// [`divide`](#tymethod.divide), [`multiply`](#tymethod.multiply) and
+  /// [`subtract`](#tymethod.subtract).
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.validate(x, y)?;
+    if y == 0 {
+      return Err(CalculatorError::DivisionByZero { dividend: x });
+    }
+    x.checked_rem(y)
+      .ok_or(CalculatorError::Overflow { op: "modulo", x, y })?;
+    let quotient = self.divide(x, y);
+    Ok(self.subtract(x, self.multiply(quotient, y)))
+  }
+
+  /// Returns the quotient and remainder of dividing `x` by `y`, or an error if `y` is zero or the
+  /// division overflows (`i32::MIN / -1`).
+  ///
+  /// By default this checks for both before computing the quotient with
+  /// [`divide`](#tymethod.divide) and the remainder like
+  /// [`checked_modulo`](#method.checked_modulo).
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.validate(x, y)?;
+    if y == 0 {
+      return Err(CalculatorError::DivisionByZero { dividend: x });
+    }
+    x.checked_div(y)
+      .ok_or(CalculatorError::Overflow { op: "divide", x, y })?;
+    let quotient = self.divide(x, y);
+    Ok((quotient, self.subtract(x, self.multiply(quotient, y))))
+  }
+
+  /// Returns the greatest common divisor of `x` and `y`, which is never negative except that
+  /// `gcd(i32::MIN, 0)` and `gcd(i32::MIN, i32::MIN)` wrap to `i32::MIN`.
+  ///
+  /// By default this runs Euclid's algorithm on the absolute values.
+  fn gcd(&self, x: i32, y: i32) -> i32 {
+    let (mut a, mut b) = (x.unsigned_abs(), y.unsigned_abs());
+    while b != 0 {
+      (a, b) = (b, a % b);
+    }
+    a as i32
+  }
+
+  /// Returns `a * b + c`, or an error if `a * b` or the sum doesn't fit in `i32`.
+  ///
+  /// By default this is [`checked_multiply`](#method.checked_multiply) followed by
+  /// [`checked_add`](#method.checked_add), so an error names the step that failed and its operands.
+  /// Backends with a fused multiply-add can override it, e.g. to only check the final result.
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    let product = self.checked_multiply(a, b)?;
+    self.checked_add(product, c)
+  }
+
+  /// Returns the sum of `x` and `y` as an `i64`, saturating at `i64::MIN`/`i64::MAX`.
+  ///
+  /// This lets sums of many `i32`s be accumulated without overflowing `i32`. By default it computes
+  /// the result locally without calling the other methods; backends that can add wider integers
+  /// should override it.
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    x.saturating_add(y)
+  }
+}
+
+/// Shorthand for an [`I32Calculator`](trait.I32Calculator.html) that can be shared between threads,
+/// so that signatures can use `dyn SharedCalculator` instead of `dyn I32Calculator + Send + Sync`.
+///
+/// This is implemented for every such calculator. Every calculator is already `'static`, see
+/// [`AsAny`](trait.AsAny.html).
+pub trait SharedCalculator: I32Calculator + Send + Sync {}
+
+impl<T: I32Calculator + Send + Sync + ?Sized> SharedCalculator for T {}
+
+/// Supertrait of [`I32Calculator`](trait.I32Calculator.html) that allows a calculator trait object
+/// to be downcast to its concrete type.
+///
+/// This is implemented for every `'static` type.
+pub trait AsAny: core::any::Any {
+  /// Returns `self` as a [`dyn Any`](core::any::Any).
+  fn as_any(&self) -> &dyn core::any::Any;
+}
+
+impl<T: core::any::Any> AsAny for T {
+  fn as_any(&self) -> &dyn core::any::Any {
+    self
+  }
+}
+
+/// Defines an [`I32Calculator`](trait.I32Calculator.html) from a closure for each operation.
+///
+/// Closures can't capture anything since they are coerced to `fn(i32, i32) -> i32`. When preceded
+/// by a struct declaration, e.g. `calculator! { struct Native; add => |x, y| x + y, ... }`, this
+/// defines that struct. Otherwise it evaluates to an instance of an anonymous calculator.
+///
+/// ```
+/// use rust_mock::{calculator, I32Calculator};
+///
+/// let calculator = calculator! {
+///   add => |x, y| x + y,
+///   subtract => |x, y| x - y,
+///   multiply => |x, y| x * y,
+///   divide => |x, y| x / y,
+/// };
+///
+/// assert_eq!(calculator.multiply(6, 7), 42);
+/// ```
+#[macro_export]
+macro_rules! calculator {
+  ($vis:vis struct $name:ident; $($op:ident => $f:expr),* $(,)?) => {
+    $vis struct $name;
+
+    impl $crate::I32Calculator for $name {
+      $(
+        fn $op(&self, x: i32, y: i32) -> i32 {
+          let f: fn(i32, i32) -> i32 = $f;
+          f(x, y)
+        }
+      )*
+    }
+  };
+  ($($op:ident => $f:expr),* $(,)?) => {{
+    $crate::calculator! { struct Calculator; $($op => $f),* }
+    Calculator
+  }};
+}
+
+/// Defines a decorator that wraps an [`I32Calculator`](trait.I32Calculator.html) and calls hooks
+/// around each operation.
+///
+/// `decorator! { Name { field: Type, .. }, before: |this, op, x, y| ..., after: |this, op, result| ... }`
+/// defines a `struct Name<C: I32Calculator>` with a public `i32_calculator: C` field and the given
+/// public state fields, which can be left out along with their braces. Its operations call
+/// `before` with the decorator, the [`OpKind`](enum.OpKind.html) and the operands, delegate to the
+/// wrapped calculator, and then return what `after` returns for the decorator, the kind and the
+/// result. The checked methods call the same hooks, with `after` only called on success, and
+/// `validate`, `supports` and `backend_name` are forwarded to the wrapped calculator.
+///
+/// Like with [`calculator!`](macro.calculator.html), the hooks can't capture anything since they are
+/// coerced to function pointers, so any state they need goes in the fields, e.g. in a `Cell`.
+///
+/// ```
+/// use core::cell::Cell;
+/// use rust_mock::{decorator, I32Calculator, IdentityCalculator};
+///
+/// decorator! {
+///   Doubling { calls: Cell<usize> },
+///   before: |this, _, _, _| this.calls.set(this.calls.get() + 1),
+///   after: |_, _, result| result * 2,
+/// }
+///
+/// let calculator = Doubling {
+///   i32_calculator: IdentityCalculator,
+///   calls: Cell::new(0),
+/// };
+///
+/// assert_eq!(calculator.multiply(6, 7), 84);
+/// assert_eq!(calculator.checked_add(1, 2), Ok(6));
+/// assert_eq!(calculator.calls.get(), 2);
+/// ```
+#[macro_export]
+macro_rules! decorator {
+  (
+    $vis:vis $name:ident $({ $($field:ident: $ty:ty),* $(,)? })?,
+    before: $before:expr,
+    after: $after:expr $(,)?
+  ) => {
+    $vis struct $name<C: $crate::I32Calculator> {
+      pub i32_calculator: C,
+      $($(pub $field: $ty,)*)?
+    }
+
+    impl<C: $crate::I32Calculator> $crate::I32Calculator for $name<C> {
+      fn supports(&self, op_kind: $crate::OpKind) -> bool {
+        self.i32_calculator.supports(op_kind)
+      }
+
+      fn backend_name(&self) -> &'static str {
+        self.i32_calculator.backend_name()
+      }
+
+      fn validate(&self, x: i32, y: i32) -> Result<(), $crate::CalculatorError> {
+        self.i32_calculator.validate(x, y)
+      }
+
+      fn add(&self, x: i32, y: i32) -> i32 {
+        $crate::decorator!(@call self, $before, $after, Add, add, x, y)
+      }
+
+      fn subtract(&self, x: i32, y: i32) -> i32 {
+        $crate::decorator!(@call self, $before, $after, Subtract, subtract, x, y)
+      }
+
+      fn multiply(&self, x: i32, y: i32) -> i32 {
+        $crate::decorator!(@call self, $before, $after, Multiply, multiply, x, y)
+      }
+
+      fn divide(&self, x: i32, y: i32) -> i32 {
+        $crate::decorator!(@call self, $before, $after, Divide, divide, x, y)
+      }
+
+      fn checked_add(&self, x: i32, y: i32) -> Result<i32, $crate::CalculatorError> {
+        $crate::decorator!(@checked self, $before, $after, Add, checked_add, x, y)
+      }
+
+      fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, $crate::CalculatorError> {
+        $crate::decorator!(@checked self, $before, $after, Subtract, checked_subtract, x, y)
+      }
+
+      fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, $crate::CalculatorError> {
+        $crate::decorator!(@checked self, $before, $after, Multiply, checked_multiply, x, y)
+      }
+
+      fn checked_divide(&self, x: i32, y: i32) -> Result<i32, $crate::CalculatorError> {
+        $crate::decorator!(@checked self, $before, $after, Divide, checked_divide, x, y)
+      }
+    }
+  };
+  (@call $self:ident, $before:expr, $after:expr, $kind:ident, $op:ident, $x:ident, $y:ident) => {{
+    let before: fn(&Self, $crate::OpKind, i32, i32) = $before;
+    let after: fn(&Self, $crate::OpKind, i32) -> i32 = $after;
+    before($self, $crate::OpKind::$kind, $x, $y);
+    let result = $self.i32_calculator.$op($x, $y);
+    after($self, $crate::OpKind::$kind, result)
+  }};
+  (@checked $self:ident, $before:expr, $after:expr, $kind:ident, $op:ident, $x:ident, $y:ident) => {{
+    let before: fn(&Self, $crate::OpKind, i32, i32) = $before;
+    let after: fn(&Self, $crate::OpKind, i32) -> i32 = $after;
+    before($self, $crate::OpKind::$kind, $x, $y);
+    let result = $self.i32_calculator.$op($x, $y)?;
+    Ok(after($self, $crate::OpKind::$kind, result))
+  }};
 }
 
 /// Toy client implementation of [`I32Calculator`](trait.I32Calculator.html) that panics when called.
 ///
 /// This would be used by the real application, but never during unit testing.
+///
+/// With the `real-impl` feature it does the arithmetic itself instead, wrapping on overflow like
+/// [`IdentityCalculator`](struct.IdentityCalculator.html).
 pub struct ExternalI32Calculator;
+
+impl ExternalI32Calculator {
+  /// Returns a builder for an [`ExternalI32Calculator`](struct.ExternalI32Calculator.html) whose
+  /// results are kept within configured bounds.
+  pub fn builder() -> ExternalI32CalculatorBuilder {
+    ExternalI32CalculatorBuilder::default()
+  }
+}
+
+#[cfg(not(feature = "real-impl"))]
 impl I32Calculator for ExternalI32Calculator {
+  fn backend_name(&self) -> &'static str {
+    "external"
+  }
+
   fn add(&self, _x: i32, _y: i32) -> i32 {
     panic!("Can't call this in unit tests!")
   }
@@ -104,71 +476,8480 @@ impl I32Calculator for ExternalI32Calculator {
   }
 }
 
-/// struct that has an [`I32Calculator`](trait.I32Calculator.html)
-/// [`i32_calculator`](struct.Application.html#structfield.i32_calculator) field.
-pub struct Application {
-  pub i32_calculator: Box<dyn I32Calculator>,
+#[cfg(feature = "real-impl")]
+impl I32Calculator for ExternalI32Calculator {
+  fn backend_name(&self) -> &'static str {
+    "external"
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_add(y)
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_sub(y)
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_mul(y)
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_div(y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    if y == 0 {
+      return Err(CalculatorError::DivisionByZero { dividend: x });
+    }
+    let quotient = x
+      .checked_div(y)
+      .ok_or(CalculatorError::Overflow { op: "divide", x, y })?;
+    Ok((quotient, x % y))
+  }
 }
 
-impl Application {
-  /// An important bit of application logic that makes use of the `I32Calculator` interface.
-  ///
-  /// This will be unit tested.
-  pub fn cool_algorithm(&self, x: i32) -> i32 {
-    let mut output = x;
+/// Lets a calculator be shared between decorators, each holding an `Rc` to it.
+///
+/// Every method is forwarded to the shared calculator, except that
+/// [`boxed_clone`](trait.I32Calculator.html#method.boxed_clone) returns another `Rc` to it.
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator + ?Sized> I32Calculator for alloc::rc::Rc<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    (**self).supports(op_kind)
+  }
 
-    output = self.i32_calculator.add(output, 0);
-    output = self.i32_calculator.subtract(output, 0);
-    output = self.i32_calculator.multiply(output, 1);
-    output = self.i32_calculator.divide(output, 1);
+  fn backend_name(&self) -> &'static str {
+    (**self).backend_name()
+  }
 
-    output
+  fn estimate(&self, op: &Operation) -> Option<i32> {
+    (**self).estimate(op)
+  }
+
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    Some(Box::new(self.clone()))
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    (**self).validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    (**self).add(x, y)
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    (**self).subtract(x, y)
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    (**self).multiply(x, y)
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    (**self).divide(x, y)
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    (**self).checked_add(x, y)
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    (**self).checked_subtract(x, y)
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    (**self).checked_multiply(x, y)
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    (**self).checked_divide(x, y)
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    (**self).checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    (**self).divmod(x, y)
+  }
+
+  fn gcd(&self, x: i32, y: i32) -> i32 {
+    (**self).gcd(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    (**self).multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    (**self).add_i64(x, y)
   }
 }
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use mockall::predicate;
+/// [`I32Calculator`](trait.I32Calculator.html) that does the arithmetic itself, making
+/// [`cool_algorithm`](struct.Application.html#method.cool_algorithm) the identity function.
+///
+/// Like `i32`'s `wrapping_*` methods, results wrap on overflow and dividing by zero panics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdentityCalculator;
+impl I32Calculator for IdentityCalculator {
+  fn backend_name(&self) -> &'static str {
+    "identity"
+  }
 
-  #[test]
-  fn cool_algorithm_does_nothing() {
-    let number = 100;
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    Some(Box::new(*self))
+  }
 
-    // mock object that is I32Calculator
-    let mut mock_i32_calculator = MockI32Calculator::new();
+  fn add(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_add(y)
+  }
 
-    // set up our expectations
-    mock_i32_calculator
-      .expect_add()
-      .times(1)
-      .with(predicate::eq(number), predicate::eq(0))
-      .return_const(number);
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_sub(y)
+  }
 
-    mock_i32_calculator
-      .expect_subtract()
-      .times(1)
-      .with(predicate::eq(number), predicate::eq(0))
-      .return_const(number);
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_mul(y)
+  }
 
-    mock_i32_calculator
-      .expect_multiply()
-      .times(1)
-      .with(predicate::eq(number), predicate::eq(1))
-      .return_const(number);
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    x.wrapping_div(y)
+  }
+}
 
-    mock_i32_calculator
-      .expect_divide()
-      .times(1)
-      .with(predicate::eq(number), predicate::eq(1))
-      .return_const(number);
+/// [`I32Calculator`](trait.I32Calculator.html) that ignores its operands and always returns the
+/// same result for each kind of operation, for stubs that don't need expectations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstantCalculator {
+  add: i32,
+  subtract: i32,
+  multiply: i32,
+  divide: i32,
+}
 
-    // create our application with our mock calculator
-    let app = Application {
-      i32_calculator: Box::new(mock_i32_calculator),
-    };
+impl ConstantCalculator {
+  /// Returns a calculator whose methods return the given constants.
+  pub fn new(add: i32, subtract: i32, multiply: i32, divide: i32) -> Self {
+    ConstantCalculator {
+      add,
+      subtract,
+      multiply,
+      divide,
+    }
+  }
+}
 
-    // run our unit test of the cool_algorithm
-    assert_eq!(app.cool_algorithm(number), number);
+impl From<[i32; 4]> for ConstantCalculator {
+  /// Returns a calculator whose methods return `[add, subtract, multiply, divide]`.
+  fn from([add, subtract, multiply, divide]: [i32; 4]) -> Self {
+    ConstantCalculator::new(add, subtract, multiply, divide)
+  }
+}
+
+impl I32Calculator for ConstantCalculator {
+  fn estimate(&self, op: &Operation) -> Option<i32> {
+    Some(op.apply(self))
+  }
+
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    Some(Box::new(*self))
+  }
+
+  fn add(&self, _x: i32, _y: i32) -> i32 {
+    self.add
+  }
+
+  fn subtract(&self, _x: i32, _y: i32) -> i32 {
+    self.subtract
+  }
+
+  fn multiply(&self, _x: i32, _y: i32) -> i32 {
+    self.multiply
+  }
+
+  fn divide(&self, _x: i32, _y: i32) -> i32 {
+    self.divide
+  }
+}
+
+/// Builder for an [`ExternalI32Calculator`](struct.ExternalI32Calculator.html) wrapped in a
+/// [`BoundedCalculator`](struct.BoundedCalculator.html).
+///
+/// By default the bounds are the full range of `i32` and the policy is
+/// [`OverflowPolicy::Wrap`](enum.OverflowPolicy.html#variant.Wrap).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExternalI32CalculatorBuilder {
+  policy: OverflowPolicy,
+  bounds: Option<(i64, i64)>,
+}
+
+impl ExternalI32CalculatorBuilder {
+  /// Sets how results outside of the bounds are handled.
+  pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Sets the inclusive bounds that results are kept within.
+  pub fn bounds(mut self, min: i64, max: i64) -> Self {
+    self.bounds = Some((min, max));
+    self
+  }
+
+  /// Validates the configuration and builds the calculator.
+  pub fn build(self) -> Result<BoundedCalculator<ExternalI32Calculator>, ConfigError> {
+    let (min, max) = self.bounds.unwrap_or((i32::MIN.into(), i32::MAX.into()));
+    BoundedCalculator::new(ExternalI32Calculator, min, max, self.policy)
+  }
+}
+
+/// How a result that falls outside of a calculator's bounds is brought back within them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Deserialize),
+  serde(rename_all = "snake_case")
+)]
+pub enum OverflowPolicy {
+  /// Wrap around to the opposite bound, like two's complement overflow.
+  #[default]
+  Wrap,
+  /// Clamp to the nearest bound.
+  Saturate,
+}
+
+/// Error returned when a calculator's configuration is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+  /// The lower bound is greater than the upper bound.
+  InvertedBounds { min: i64, max: i64 },
+  /// A bound lies outside of the range of `i32`, so no result could ever reach it.
+  BoundsOutOfRange { min: i64, max: i64 },
+}
+
+impl core::fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ConfigError::InvertedBounds { min, max } => {
+        write!(f, "lower bound {min} is greater than upper bound {max}")
+      }
+      ConfigError::BoundsOutOfRange { min, max } => {
+        write!(f, "bounds {min}..={max} do not fit in i32")
+      }
+    }
+  }
+}
+
+impl core::error::Error for ConfigError {}
+
+/// Calculator that a [`CalculatorConfig`](struct.CalculatorConfig.html) builds on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Deserialize),
+  serde(rename_all = "snake_case")
+)]
+pub enum CalculatorBackend {
+  /// [`ExternalI32Calculator`](struct.ExternalI32Calculator.html).
+  #[default]
+  External,
+  /// [`IdentityCalculator`](struct.IdentityCalculator.html).
+  Identity,
+}
+
+/// Configuration for the calculator of an [`Application`](struct.Application.html), which can be
+/// converted into one with `Application::try_from`.
+///
+/// The backend is wrapped in a [`BoundedCalculator`](struct.BoundedCalculator.html); without
+/// `bounds` it uses the full range of `i32`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct CalculatorConfig {
+  pub backend: CalculatorBackend,
+  pub overflow_policy: OverflowPolicy,
+  pub bounds: Option<(i64, i64)>,
+}
+
+#[cfg(feature = "alloc")]
+impl CalculatorConfig {
+  /// Returns the configured calculator, or an error if the bounds are invalid.
+  pub fn build(self) -> Result<Box<dyn I32Calculator>, ConfigError> {
+    let (min, max) = self.bounds.unwrap_or((i32::MIN.into(), i32::MAX.into()));
+    Ok(match self.backend {
+      CalculatorBackend::External => Box::new(BoundedCalculator::new(
+        ExternalI32Calculator,
+        min,
+        max,
+        self.overflow_policy,
+      )?),
+      CalculatorBackend::Identity => Box::new(BoundedCalculator::new(
+        IdentityCalculator,
+        min,
+        max,
+        self.overflow_policy,
+      )?),
+    })
+  }
+}
+
+/// Error returned when a calculation can't produce a result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CalculatorError {
+  /// `dividend` was divided by zero.
+  DivisionByZero { dividend: i32 },
+  /// The result of `op` on `x` and `y` doesn't fit in `i32`.
+  Overflow { op: &'static str, x: i32, y: i32 },
+  /// The square root of the negative `radicand` was requested.
+  NegativeSquareRoot { radicand: i32 },
+  /// The operands `x` and `y` were rejected by [`validate`](trait.I32Calculator.html#method.validate).
+  InvalidOperands { x: i32, y: i32 },
+  /// The service failed temporarily, so the same call may succeed if retried.
+  Transient,
+  /// The calculator's call budget has been used up, see [`BudgetedCalculator`](struct.BudgetedCalculator.html).
+  BudgetExhausted,
+  /// The call was cancelled before it completed, see [`CancellableCalculator`](struct.CancellableCalculator.html).
+  Cancelled,
+  /// The result was negative, see [`NonNegativeCalculator`](struct.NonNegativeCalculator.html).
+  NegativeResult,
+  /// The same call gave different results, see
+  /// [`DivergenceDetector`](struct.DivergenceDetector.html).
+  Nondeterministic,
+  /// `result` was smaller than the `previous` one, see
+  /// [`MonotonicCalculator`](struct.MonotonicCalculator.html).
+  NonMonotonic { previous: i32, result: i32 },
+  /// The deadline passed before the call, see
+  /// [`DeadlineCalculator`](struct.DeadlineCalculator.html).
+  DeadlineExceeded,
+  /// A wrapped calculator failed with the boxed error, which is also the
+  /// [`source`](core::error::Error::source) of this one, e.g. once
+  /// [`AsyncRetryingCalculator`](struct.AsyncRetryingCalculator.html) gives up.
+  #[cfg(feature = "alloc")]
+  Underlying(Box<CalculatorError>),
+}
+
+impl core::fmt::Display for CalculatorError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      CalculatorError::DivisionByZero { dividend } => {
+        write!(f, "attempted to divide {dividend} by zero")
+      }
+      CalculatorError::Overflow { op, x, y } => write!(f, "{op} of {x} and {y} overflows i32"),
+      CalculatorError::NegativeSquareRoot { radicand } => {
+        write!(f, "{radicand} has no real square root")
+      }
+      CalculatorError::InvalidOperands { x, y } => write!(f, "operands {x} and {y} were rejected"),
+      CalculatorError::Transient => write!(f, "calculator failed temporarily"),
+      CalculatorError::BudgetExhausted => write!(f, "calculator call budget exhausted"),
+      CalculatorError::Cancelled => write!(f, "calculator call was cancelled"),
+      CalculatorError::NegativeResult => write!(f, "result is negative"),
+      CalculatorError::Nondeterministic => write!(f, "calculator gave different results"),
+      CalculatorError::NonMonotonic { previous, result } => {
+        write!(
+          f,
+          "result {result} is smaller than the previous result {previous}"
+        )
+      }
+      CalculatorError::DeadlineExceeded => write!(f, "calculator deadline exceeded"),
+      #[cfg(feature = "alloc")]
+      CalculatorError::Underlying(error) => write!(f, "wrapped calculator failed: {error}"),
+    }
+  }
+}
+
+impl core::error::Error for CalculatorError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    match self {
+      #[cfg(feature = "alloc")]
+      CalculatorError::Underlying(error) => Some(&**error),
+      _ => None,
+    }
+  }
+}
+
+/// Error returned by [`Application::run_script`](struct.Application.html#method.run_script).
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptError {
+  /// The script has no tokens.
+  Empty,
+  /// A token where a number was expected isn't an `i32`.
+  InvalidNumber { token: String },
+  /// A token where an operator was expected isn't one of `+`, `-`, `*` or `/`.
+  UnknownOperator { token: String },
+  /// The script ends with an operator.
+  MissingOperand,
+  /// The calculator failed to perform an operation.
+  Calculator(CalculatorError),
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for ScriptError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ScriptError::Empty => write!(f, "script is empty"),
+      ScriptError::InvalidNumber { token } => write!(f, "{token:?} is not a valid number"),
+      ScriptError::UnknownOperator { token } => write!(f, "{token:?} is not a valid operator"),
+      ScriptError::MissingOperand => write!(f, "script ends with an operator"),
+      ScriptError::Calculator(error) => write!(f, "{error}"),
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for ScriptError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    match self {
+      ScriptError::Calculator(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CalculatorError> for ScriptError {
+  fn from(error: CalculatorError) -> Self {
+    ScriptError::Calculator(error)
+  }
+}
+
+/// A token of a reverse Polish notation expression, see
+/// [`Application::eval_rpn`](struct.Application.html#method.eval_rpn).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpnToken {
+  /// Pushes a number onto the stack.
+  Num(i32),
+  /// Pops two numbers and pushes the result of `+`, `-`, `*` or `/` on them.
+  Op(char),
+}
+
+/// Error returned by [`Application::eval_rpn`](struct.Application.html#method.eval_rpn).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RpnError {
+  /// An operator was applied with fewer than two numbers on the stack, or there were no tokens.
+  StackUnderflow,
+  /// More than one number was left on the stack at the end.
+  LeftoverOperands { count: usize },
+  /// An operator isn't one of `+`, `-`, `*` or `/`.
+  UnknownOperator { op: char },
+  /// The calculator failed to perform an operation.
+  Calculator(CalculatorError),
+}
+
+impl core::fmt::Display for RpnError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      RpnError::StackUnderflow => write!(f, "not enough operands on the stack"),
+      RpnError::LeftoverOperands { count } => write!(f, "{count} operands left on the stack"),
+      RpnError::UnknownOperator { op } => write!(f, "{op:?} is not a valid operator"),
+      RpnError::Calculator(error) => write!(f, "{error}"),
+    }
+  }
+}
+
+impl core::error::Error for RpnError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    match self {
+      RpnError::Calculator(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<CalculatorError> for RpnError {
+  fn from(error: CalculatorError) -> Self {
+    RpnError::Calculator(error)
+  }
+}
+
+#[cfg(feature = "std")]
+impl From<CalculatorError> for std::io::Error {
+  fn from(error: CalculatorError) -> Self {
+    let kind = match error {
+      CalculatorError::DivisionByZero { .. } | CalculatorError::Overflow { .. } => {
+        std::io::ErrorKind::InvalidInput
+      }
+      CalculatorError::NegativeSquareRoot { .. } => std::io::ErrorKind::InvalidInput,
+      CalculatorError::InvalidOperands { .. } => std::io::ErrorKind::InvalidInput,
+      CalculatorError::Transient => std::io::ErrorKind::Interrupted,
+      CalculatorError::BudgetExhausted => std::io::ErrorKind::Other,
+      CalculatorError::Cancelled => std::io::ErrorKind::Other,
+      CalculatorError::NegativeResult => std::io::ErrorKind::InvalidInput,
+      CalculatorError::Nondeterministic => std::io::ErrorKind::Other,
+      CalculatorError::NonMonotonic { .. } => std::io::ErrorKind::InvalidInput,
+      CalculatorError::DeadlineExceeded => std::io::ErrorKind::TimedOut,
+      CalculatorError::Underlying(ref underlying) => {
+        std::io::Error::from((**underlying).clone()).kind()
+      }
+    };
+    std::io::Error::new(kind, error)
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and keeps its results within inclusive
+/// bounds according to an [`OverflowPolicy`](enum.OverflowPolicy.html).
+pub struct BoundedCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  min: i32,
+  max: i32,
+  policy: OverflowPolicy,
+}
+
+impl<C: I32Calculator> BoundedCalculator<C> {
+  /// Returns a calculator that keeps the results of `i32_calculator` within `min..=max`.
+  ///
+  /// Fails if the bounds are inverted or don't fit in `i32`.
+  pub fn new(
+    i32_calculator: C,
+    min: i64,
+    max: i64,
+    policy: OverflowPolicy,
+  ) -> Result<Self, ConfigError> {
+    if min > max {
+      return Err(ConfigError::InvertedBounds { min, max });
+    }
+    let (Ok(lower), Ok(upper)) = (i32::try_from(min), i32::try_from(max)) else {
+      return Err(ConfigError::BoundsOutOfRange { min, max });
+    };
+    Ok(BoundedCalculator {
+      i32_calculator,
+      min: lower,
+      max: upper,
+      policy,
+    })
+  }
+
+  fn bound(&self, result: i32) -> i32 {
+    if (self.min..=self.max).contains(&result) {
+      return result;
+    }
+    match self.policy {
+      OverflowPolicy::Wrap => {
+        let (min, max) = (i64::from(self.min), i64::from(self.max));
+        (min + (i64::from(result) - min).rem_euclid(max - min + 1)) as i32
+      }
+      OverflowPolicy::Saturate => result.clamp(self.min, self.max),
+    }
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for BoundedCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.bound(self.i32_calculator.add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.bound(self.i32_calculator.subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.bound(self.i32_calculator.multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.bound(self.i32_calculator.divide(x, y))
+  }
+}
+
+/// Calculator that returns the results of a primary calculator while comparing them against a
+/// shadow calculator, e.g. a new backend that is being rolled out.
+///
+/// Disagreements are logged with `log::warn!` and counted, but never fail the call.
+pub struct ShadowCalculator<A: I32Calculator, B: I32Calculator> {
+  primary: A,
+  shadow: B,
+  mismatches: core::cell::Cell<usize>,
+}
+
+impl<A: I32Calculator, B: I32Calculator> ShadowCalculator<A, B> {
+  /// Returns a calculator that answers with `primary` and checks its answers against `shadow`.
+  pub fn new(primary: A, shadow: B) -> Self {
+    ShadowCalculator {
+      primary,
+      shadow,
+      mismatches: core::cell::Cell::new(0),
+    }
+  }
+
+  /// Returns how many results the shadow calculator disagreed on.
+  pub fn mismatches(&self) -> usize {
+    self.mismatches.get()
+  }
+
+  fn compare(&self, operation: Operation) -> i32 {
+    let expected = operation.apply(&self.primary);
+    let actual = operation.apply(&self.shadow);
+    if expected != actual {
+      log::warn!("shadow calculator returned {actual} for {operation:?}, expected {expected}");
+      self.mismatches.set(self.mismatches.get() + 1);
+    }
+    expected
+  }
+}
+
+impl<A: I32Calculator, B: I32Calculator> I32Calculator for ShadowCalculator<A, B> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.compare(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.compare(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.compare(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.compare(Operation::Divide(x, y))
+  }
+}
+
+/// Adapter that shares a single [`I32Calculator`](trait.I32Calculator.html) behind a mutex, e.g.
+/// between several [`Application`](struct.Application.html)s or threads.
+///
+/// Each call locks the mutex for its whole duration, so calls are serialized. This also means that
+/// a wrapped calculator whose methods call back into the same shared calculator (directly or
+/// through a clone) will deadlock.
+///
+/// Every method is forwarded to the shared calculator, except that
+/// [`boxed_clone`](trait.I32Calculator.html#method.boxed_clone) returns another handle to it.
+#[cfg(feature = "std")]
+pub struct SharedMutCalculator<C: I32Calculator>(pub std::sync::Arc<std::sync::Mutex<C>>);
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> SharedMutCalculator<C> {
+  /// Returns a shareable adapter that owns `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    SharedMutCalculator(std::sync::Arc::new(std::sync::Mutex::new(i32_calculator)))
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, C> {
+    self.0.lock().expect("shared calculator mutex was poisoned")
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> Clone for SharedMutCalculator<C> {
+  fn clone(&self) -> Self {
+    SharedMutCalculator(self.0.clone())
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> I32Calculator for SharedMutCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.lock().supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.lock().backend_name()
+  }
+
+  fn estimate(&self, op: &Operation) -> Option<i32> {
+    self.lock().estimate(op)
+  }
+
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    Some(Box::new(self.clone()))
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.lock().validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.lock().add(x, y)
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.lock().subtract(x, y)
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.lock().multiply(x, y)
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.lock().divide(x, y)
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.lock().checked_add(x, y)
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.lock().checked_subtract(x, y)
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.lock().checked_multiply(x, y)
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.lock().checked_divide(x, y)
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.lock().checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.lock().divmod(x, y)
+  }
+
+  fn gcd(&self, x: i32, y: i32) -> i32 {
+    self.lock().gcd(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.lock().multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.lock().add_i64(x, y)
+  }
+}
+
+/// Calculator that falls back to a secondary calculator whenever the primary one panics, or for
+/// fallible methods, returns an error.
+///
+/// Every method is forwarded this way, except that an operation is supported if either calculator
+/// supports it and the backend name is the primary calculator's.
+///
+/// This makes it possible to wrap [`ExternalI32Calculator`](struct.ExternalI32Calculator.html) with
+/// a safe alternative. Panics are caught with [`std::panic::catch_unwind`], so the primary
+/// calculator must tolerate being used again after one of its calls panicked.
+///
+/// Not available on `wasm32` targets, where panics abort instead of unwinding.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub struct FallbackCalculator<P: I32Calculator, S: I32Calculator> {
+  primary: P,
+  secondary: S,
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl<P: I32Calculator, S: I32Calculator> FallbackCalculator<P, S> {
+  /// Returns a calculator that tries `primary` before `secondary`.
+  pub fn new(primary: P, secondary: S) -> Self {
+    FallbackCalculator { primary, secondary }
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    self.call(|calculator| operation.apply(calculator))
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    self.call_checked(|calculator| operation.apply_checked(calculator))
+  }
+
+  fn call<T>(&self, call: impl Fn(&dyn I32Calculator) -> T) -> T {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| call(&self.primary)))
+      .unwrap_or_else(|_| call(&self.secondary))
+  }
+
+  fn call_checked<T>(
+    &self,
+    call: impl Fn(&dyn I32Calculator) -> Result<T, CalculatorError>,
+  ) -> Result<T, CalculatorError> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| call(&self.primary))) {
+      Ok(Ok(result)) => Ok(result),
+      Ok(Err(_)) | Err(_) => call(&self.secondary),
+    }
+  }
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl<P: I32Calculator, S: I32Calculator> I32Calculator for FallbackCalculator<P, S> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.primary.supports(op_kind) || self.secondary.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.primary.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.call_checked(|calculator| calculator.validate(x, y))
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.call_checked(|calculator| calculator.checked_modulo(x, y))
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.call_checked(|calculator| calculator.divmod(x, y))
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.call_checked(|calculator| calculator.multiply_add(a, b, c))
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.call(|calculator| calculator.add_i64(x, y))
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and makes a fraction of its checked calls fail
+/// with [`CalculatorError::Transient`](enum.CalculatorError.html#variant.Transient), for chaos
+/// testing.
+///
+/// Failures are decided by a pseudorandom generator seeded with `seed`, so the same seed and calls
+/// always fail the same way. Only the fallible methods, i.e. the `checked_*` methods,
+/// [`divmod`](trait.I32Calculator.html#method.divmod) and
+/// [`multiply_add`](trait.I32Calculator.html#method.multiply_add), can fail, each call rolling
+/// once; the infallible methods always call the wrapped calculator.
+pub struct FlakyCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  failure_rate: f64,
+  state: core::cell::Cell<u64>,
+}
+
+impl<C: I32Calculator> FlakyCalculator<C> {
+  /// Returns a calculator that fails each checked call of `i32_calculator` with probability
+  /// `failure_rate`, where `0.0` never fails and `1.0` always does.
+  pub fn new(i32_calculator: C, failure_rate: f64, seed: u64) -> Self {
+    FlakyCalculator {
+      i32_calculator,
+      failure_rate,
+      state: core::cell::Cell::new(seed),
+    }
+  }
+
+  // SplitMix64, which is plenty for picking failures and keeps the crate free of an rng dependency
+  fn next_u64(&self) -> u64 {
+    let state = self.state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+    self.state.set(state);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  fn roll(&self) -> Result<&C, CalculatorError> {
+    // uniform in [0, 1)
+    let roll = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    if roll < self.failure_rate {
+      return Err(CalculatorError::Transient);
+    }
+    Ok(&self.i32_calculator)
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    operation.apply_checked(self.roll()?)
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for FlakyCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.i32_calculator.add(x, y)
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.i32_calculator.subtract(x, y)
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.i32_calculator.multiply(x, y)
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.i32_calculator.divide(x, y)
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.roll()?.checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.roll()?.divmod(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.roll()?.multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.i32_calculator.add_i64(x, y)
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and allows only a limited number of calls to
+/// it in total, e.g. to cap spending on a paid service.
+///
+/// Every call that reaches the wrapped calculator costs one unit, including
+/// [`checked_modulo`](trait.I32Calculator.html#method.checked_modulo),
+/// [`divmod`](trait.I32Calculator.html#method.divmod) and
+/// [`multiply_add`](trait.I32Calculator.html#method.multiply_add), which are forwarded as one call.
+/// Once the budget is used up, the fallible methods return
+/// [`CalculatorError::BudgetExhausted`](enum.CalculatorError.html#variant.BudgetExhausted) without
+/// calling the wrapped calculator, and the infallible methods panic.
+pub struct BudgetedCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  remaining: core::cell::Cell<usize>,
+}
+
+impl<C: I32Calculator> BudgetedCalculator<C> {
+  /// Returns a calculator that allows `budget` calls to `i32_calculator`.
+  pub fn new(i32_calculator: C, budget: usize) -> Self {
+    BudgetedCalculator {
+      i32_calculator,
+      remaining: core::cell::Cell::new(budget),
+    }
+  }
+
+  /// Returns how many more calls are allowed.
+  pub fn remaining(&self) -> usize {
+    self.remaining.get()
+  }
+
+  fn spend(&self) -> Result<&C, CalculatorError> {
+    let remaining = self
+      .remaining
+      .get()
+      .checked_sub(1)
+      .ok_or(CalculatorError::BudgetExhausted)?;
+    self.remaining.set(remaining);
+    Ok(&self.i32_calculator)
+  }
+
+  fn spend_or_panic(&self) -> &C {
+    match self.spend() {
+      Ok(calculator) => calculator,
+      Err(error) => panic!("{error}"),
+    }
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    operation.apply(self.spend_or_panic())
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    operation.apply_checked(self.spend()?)
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for BudgetedCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.spend()?.checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.spend()?.divmod(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.spend()?.multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.spend_or_panic().add_i64(x, y)
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and stops calling it once a deadline has
+/// passed, e.g. for time-bounded batch jobs.
+///
+/// From the deadline on, the fallible methods, including
+/// [`checked_modulo`](trait.I32Calculator.html#method.checked_modulo) and
+/// [`divmod`](trait.I32Calculator.html#method.divmod), return
+/// [`CalculatorError::DeadlineExceeded`](enum.CalculatorError.html#variant.DeadlineExceeded)
+/// without calling the wrapped calculator, and the infallible methods panic.
+#[cfg(feature = "std")]
+pub struct DeadlineCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  deadline: Instant,
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> DeadlineCalculator<C> {
+  /// Returns a calculator that calls `i32_calculator` until `deadline`.
+  pub fn new(i32_calculator: C, deadline: Instant) -> Self {
+    DeadlineCalculator {
+      i32_calculator,
+      deadline,
+    }
+  }
+
+  fn calculator(&self) -> Result<&C, CalculatorError> {
+    if Instant::now() >= self.deadline {
+      return Err(CalculatorError::DeadlineExceeded);
+    }
+    Ok(&self.i32_calculator)
+  }
+
+  fn calculator_or_panic(&self) -> &C {
+    match self.calculator() {
+      Ok(calculator) => calculator,
+      Err(error) => panic!("{error}"),
+    }
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    operation.apply(self.calculator_or_panic())
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    operation.apply_checked(self.calculator()?)
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> I32Calculator for DeadlineCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.calculator()?.checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.calculator()?.divmod(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.calculator()?.multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.calculator_or_panic().add_i64(x, y)
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and rejects negative results, e.g. for
+/// counters.
+///
+/// The checked methods return
+/// [`CalculatorError::NegativeResult`](enum.CalculatorError.html#variant.NegativeResult) instead
+/// of a negative result, with [`divmod`](trait.I32Calculator.html#method.divmod) rejecting a
+/// negative quotient or remainder, and the infallible methods panic.
+pub struct NonNegativeCalculator<C: I32Calculator> {
+  i32_calculator: C,
+}
+
+impl<C: I32Calculator> NonNegativeCalculator<C> {
+  /// Returns a calculator that rejects the negative results of `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    NonNegativeCalculator { i32_calculator }
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    match Self::non_negative(operation.apply(&self.i32_calculator)) {
+      Ok(result) => result,
+      Err(error) => panic!("{operation} failed: {error}"),
+    }
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    Self::non_negative(operation.apply_checked(&self.i32_calculator)?)
+  }
+
+  fn non_negative(result: i32) -> Result<i32, CalculatorError> {
+    if result < 0 {
+      return Err(CalculatorError::NegativeResult);
+    }
+    Ok(result)
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for NonNegativeCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    Self::non_negative(self.i32_calculator.checked_modulo(x, y)?)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    let (quotient, remainder) = self.i32_calculator.divmod(x, y)?;
+    Ok((
+      Self::non_negative(quotient)?,
+      Self::non_negative(remainder)?,
+    ))
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and rejects results that are smaller than
+/// the last accepted one, e.g. for sequence generators.
+///
+/// The checked methods return
+/// [`CalculatorError::NonMonotonic`](enum.CalculatorError.html#variant.NonMonotonic) instead of
+/// such a result, and the infallible methods panic. Rejected results aren't remembered.
+///
+/// [`divmod`](trait.I32Calculator.html#method.divmod) checks its quotient, and
+/// [`multiply_add`](trait.I32Calculator.html#method.multiply_add) its final result.
+/// [`add_i64`](trait.I32Calculator.html#method.add_i64) is forwarded unchecked since its result
+/// isn't an `i32`.
+pub struct MonotonicCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  last: core::cell::Cell<Option<i32>>,
+}
+
+impl<C: I32Calculator> MonotonicCalculator<C> {
+  /// Returns a calculator that keeps the results of `i32_calculator` from decreasing.
+  pub fn new(i32_calculator: C) -> Self {
+    MonotonicCalculator {
+      i32_calculator,
+      last: core::cell::Cell::new(None),
+    }
+  }
+
+  /// Returns the last accepted result, if any.
+  pub fn last(&self) -> Option<i32> {
+    self.last.get()
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    match self.accept(operation.apply(&self.i32_calculator)) {
+      Ok(result) => result,
+      Err(error) => panic!("{operation} failed: {error}"),
+    }
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    self.accept(operation.apply_checked(&self.i32_calculator)?)
+  }
+
+  fn accept(&self, result: i32) -> Result<i32, CalculatorError> {
+    match self.last.get() {
+      Some(previous) if result < previous => {
+        Err(CalculatorError::NonMonotonic { previous, result })
+      }
+      _ => {
+        self.last.set(Some(result));
+        Ok(result)
+      }
+    }
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for MonotonicCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.accept(self.i32_calculator.checked_modulo(x, y)?)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    let (quotient, remainder) = self.i32_calculator.divmod(x, y)?;
+    Ok((self.accept(quotient)?, remainder))
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.accept(self.i32_calculator.multiply_add(a, b, c)?)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.i32_calculator.add_i64(x, y)
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and performs every operation twice to catch
+/// flaky backends.
+///
+/// When the two results differ, the checked methods return
+/// [`CalculatorError::Nondeterministic`](enum.CalculatorError.html#variant.Nondeterministic) and
+/// the infallible methods panic.
+pub struct DivergenceDetector<C: I32Calculator> {
+  i32_calculator: C,
+}
+
+impl<C: I32Calculator> DivergenceDetector<C> {
+  /// Returns a calculator that checks that `i32_calculator` always agrees with itself.
+  pub fn new(i32_calculator: C) -> Self {
+    DivergenceDetector { i32_calculator }
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    let result = operation.apply(&self.i32_calculator);
+    if operation.apply(&self.i32_calculator) != result {
+      panic!("{operation} failed: {}", CalculatorError::Nondeterministic);
+    }
+    result
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    self.call_twice(|calculator| operation.apply_checked(calculator))
+  }
+
+  fn call_twice<T: PartialEq>(
+    &self,
+    call: impl Fn(&C) -> Result<T, CalculatorError>,
+  ) -> Result<T, CalculatorError> {
+    let result = call(&self.i32_calculator);
+    if call(&self.i32_calculator) != result {
+      return Err(CalculatorError::Nondeterministic);
+    }
+    result
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for DivergenceDetector<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.call_twice(|calculator| calculator.checked_modulo(x, y))
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.call_twice(|calculator| calculator.divmod(x, y))
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.call_twice(|calculator| calculator.multiply_add(a, b, c))
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    let result = self.i32_calculator.add_i64(x, y);
+    if self.i32_calculator.add_i64(x, y) != result {
+      panic!("{x} + {y} failed: {}", CalculatorError::Nondeterministic);
+    }
+    result
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and passes every operation and its result
+/// to a callback, e.g. for monitoring.
+#[cfg(feature = "alloc")]
+pub struct ObservedCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  observer: Box<dyn Fn(Operation, i32)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> ObservedCalculator<C> {
+  /// Returns a calculator that calls `observer` after each operation of `i32_calculator`.
+  pub fn new(i32_calculator: C, observer: Box<dyn Fn(Operation, i32)>) -> Self {
+    ObservedCalculator {
+      i32_calculator,
+      observer,
+    }
+  }
+
+  fn observe(&self, operation: Operation) -> i32 {
+    let result = operation.apply(&self.i32_calculator);
+    (self.observer)(operation, result);
+    result
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> I32Calculator for ObservedCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.observe(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.observe(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.observe(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.observe(Operation::Divide(x, y))
+  }
+}
+
+/// A layer of a [`MiddlewareCalculator`](struct.MiddlewareCalculator.html) that can inspect or
+/// change each operation and its result, e.g. for logging, timing or caching.
+pub trait CalculatorMiddleware {
+  /// Performs `operation`, calling `next` to pass it on to the rest of the stack.
+  fn wrap(&self, operation: Operation, next: &dyn Fn(Operation) -> i32) -> i32;
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) in a stack of
+/// [`CalculatorMiddleware`](trait.CalculatorMiddleware.html).
+///
+/// The first middleware is the outermost, so it sees each operation first and its result last.
+#[cfg(feature = "alloc")]
+pub struct MiddlewareCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  middleware: Vec<Box<dyn CalculatorMiddleware>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> MiddlewareCalculator<C> {
+  /// Returns a calculator that runs each operation through `middleware` and then `i32_calculator`.
+  pub fn new(i32_calculator: C, middleware: Vec<Box<dyn CalculatorMiddleware>>) -> Self {
+    MiddlewareCalculator {
+      i32_calculator,
+      middleware,
+    }
+  }
+
+  fn run(&self, layer: usize, operation: Operation) -> i32 {
+    match self.middleware.get(layer) {
+      Some(middleware) => middleware.wrap(operation, &|operation| self.run(layer + 1, operation)),
+      None => operation.apply(&self.i32_calculator),
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> I32Calculator for MiddlewareCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.run(0, Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.run(0, Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.run(0, Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.run(0, Operation::Divide(x, y))
+  }
+}
+
+/// How many times each [`I32Calculator`](trait.I32Calculator.html) operation was called.
+///
+/// With the `serde` feature this serializes with the keys `add`, `subtract`, `multiply` and
+/// `divide`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CalculatorStats {
+  pub add: usize,
+  pub subtract: usize,
+  pub multiply: usize,
+  pub divide: usize,
+}
+
+impl CalculatorStats {
+  /// Counts one call of an operation of kind `op`.
+  pub fn record(&mut self, op: OpKind) {
+    match op {
+      OpKind::Add => self.add += 1,
+      OpKind::Subtract => self.subtract += 1,
+      OpKind::Multiply => self.multiply += 1,
+      OpKind::Divide => self.divide += 1,
+    }
+  }
+
+  /// Returns how many operations were called in total.
+  pub fn total(&self) -> usize {
+    self.add + self.subtract + self.multiply + self.divide
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and collects
+/// [`CalculatorStats`](struct.CalculatorStats.html) about the calls made to it.
+pub struct CountingCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  stats: core::cell::Cell<CalculatorStats>,
+}
+
+impl<C: I32Calculator> CountingCalculator<C> {
+  /// Returns a calculator that counts the calls made to `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    CountingCalculator {
+      i32_calculator,
+      stats: core::cell::Cell::new(CalculatorStats::default()),
+    }
+  }
+
+  /// Returns the calls counted so far.
+  pub fn stats(&self) -> CalculatorStats {
+    self.stats.get()
+  }
+
+  fn count(&self, operation: Operation) -> i32 {
+    let mut stats = self.stats.get();
+    stats.record(operation.kind());
+    self.stats.set(stats);
+    operation.apply(&self.i32_calculator)
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for CountingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Divide(x, y))
+  }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+  static THREAD_LOCAL_STATS: core::cell::Cell<CalculatorStats> =
+    const { core::cell::Cell::new(CalculatorStats { add: 0, subtract: 0, multiply: 0, divide: 0 }) };
+}
+
+/// Like [`CountingCalculator`](struct.CountingCalculator.html), but counts calls in thread-local
+/// storage to avoid any synchronization in hot loops.
+///
+/// Counts are per thread and shared by every `ThreadLocalCountingCalculator` on that thread,
+/// whatever calculator it wraps, so [`snapshot`](#method.snapshot) only reports the calls made from
+/// the current thread, and reports the same counts whichever `C` it is called on.
+#[cfg(feature = "std")]
+pub struct ThreadLocalCountingCalculator<C: I32Calculator>(pub C);
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> ThreadLocalCountingCalculator<C> {
+  /// Returns the calls counted by every `ThreadLocalCountingCalculator` on this thread since it
+  /// started or was last [`reset`](#method.reset).
+  pub fn snapshot() -> CalculatorStats {
+    THREAD_LOCAL_STATS.with(core::cell::Cell::get)
+  }
+
+  /// Clears the calls counted on this thread.
+  pub fn reset() {
+    THREAD_LOCAL_STATS.with(|stats| stats.set(CalculatorStats::default()));
+  }
+
+  fn count(&self, operation: Operation) -> i32 {
+    THREAD_LOCAL_STATS.with(|cell| {
+      let mut stats = cell.get();
+      stats.record(operation.kind());
+      cell.set(stats);
+    });
+    operation.apply(&self.0)
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> I32Calculator for ThreadLocalCountingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.count(Operation::Divide(x, y))
+  }
+}
+
+/// An `i32` value tagged with a unit of measurement, e.g. `Quantity<Meters>`.
+///
+/// Units are marker types that are never instantiated, so any type can be used as one.
+pub struct Quantity<Unit> {
+  value: i32,
+  unit: core::marker::PhantomData<Unit>,
+}
+
+impl<Unit> Quantity<Unit> {
+  /// Returns `value` tagged with `Unit`.
+  pub fn new(value: i32) -> Self {
+    Quantity {
+      value,
+      unit: core::marker::PhantomData,
+    }
+  }
+
+  /// Returns the untagged value.
+  pub fn value(&self) -> i32 {
+    self.value
+  }
+}
+
+impl<Unit> Clone for Quantity<Unit> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<Unit> Copy for Quantity<Unit> {}
+
+impl<Unit> PartialEq for Quantity<Unit> {
+  fn eq(&self, other: &Self) -> bool {
+    self.value == other.value
+  }
+}
+
+impl<Unit> Eq for Quantity<Unit> {}
+
+impl<Unit> core::fmt::Debug for Quantity<Unit> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_tuple("Quantity").field(&self.value).finish()
+  }
+}
+
+/// Unit of the product of a quantity in `A` and a quantity in `B`.
+pub struct Product<A, B>(core::marker::PhantomData<(A, B)>);
+
+/// Unit of the quotient of a quantity in `A` and a quantity in `B`.
+pub struct Quotient<A, B>(core::marker::PhantomData<(A, B)>);
+
+/// Adapter that performs [`I32Calculator`](trait.I32Calculator.html) operations on
+/// [`Quantity`](struct.Quantity.html)s, checking their units at compile time.
+pub struct TypedCalculator<C: I32Calculator>(pub C);
+
+impl<C: I32Calculator> TypedCalculator<C> {
+  /// Returns the sum of `x` and `y`, which must have the same unit.
+  ///
+  /// ```compile_fail
+  /// use rust_mock::{IdentityCalculator, Quantity, TypedCalculator};
+  ///
+  /// struct Meters;
+  /// struct Seconds;
+  ///
+  /// let calculator = TypedCalculator(IdentityCalculator);
+  /// calculator.add(Quantity::<Meters>::new(1), Quantity::<Seconds>::new(2));
+  /// ```
+  pub fn add<Unit>(&self, x: Quantity<Unit>, y: Quantity<Unit>) -> Quantity<Unit> {
+    Quantity::new(self.0.add(x.value, y.value))
+  }
+
+  /// Returns the difference of `x` and `y`, which must have the same unit.
+  pub fn subtract<Unit>(&self, x: Quantity<Unit>, y: Quantity<Unit>) -> Quantity<Unit> {
+    Quantity::new(self.0.subtract(x.value, y.value))
+  }
+
+  /// Returns the product of `x` and `y`.
+  pub fn multiply<A, B>(&self, x: Quantity<A>, y: Quantity<B>) -> Quantity<Product<A, B>> {
+    Quantity::new(self.0.multiply(x.value, y.value))
+  }
+
+  /// Returns the quotient of `x` and `y`.
+  pub fn divide<A, B>(&self, x: Quantity<A>, y: Quantity<B>) -> Quantity<Quotient<A, B>> {
+    Quantity::new(self.0.divide(x.value, y.value))
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and counts how often each pair of operands
+/// is passed to each operation, e.g. for profiling.
+#[cfg(feature = "std")]
+pub struct ArgHistogramCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  histograms: core::cell::RefCell<[std::collections::HashMap<(i32, i32), usize>; OpKind::COUNT]>,
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> ArgHistogramCalculator<C> {
+  /// Returns a calculator that records the operands passed to `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    ArgHistogramCalculator {
+      i32_calculator,
+      histograms: Default::default(),
+    }
+  }
+
+  /// Returns how many times each `(x, y)` pair has been passed to operations of kind `op`.
+  pub fn histogram(
+    &self,
+    op: OpKind,
+  ) -> core::cell::Ref<'_, std::collections::HashMap<(i32, i32), usize>> {
+    core::cell::Ref::map(self.histograms.borrow(), |histograms| {
+      &histograms[op.index()]
+    })
+  }
+
+  fn record(&self, operation: Operation) -> i32 {
+    *self.histograms.borrow_mut()[operation.kind().index()]
+      .entry(operation.operands())
+      .or_default() += 1;
+    operation.apply(&self.i32_calculator)
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> I32Calculator for ArgHistogramCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.record(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.record(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.record(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.record(Operation::Divide(x, y))
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and sleeps before every call, e.g. to
+/// simulate a slow backend.
+///
+/// Not available on `wasm32` targets, which can't block the current thread.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub struct DelayCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  delay: std::time::Duration,
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl<C: I32Calculator> DelayCalculator<C> {
+  /// Returns a calculator that sleeps for `delay` before each call to `i32_calculator`.
+  pub fn new(i32_calculator: C, delay: std::time::Duration) -> Self {
+    DelayCalculator {
+      i32_calculator,
+      delay,
+    }
+  }
+
+  fn delayed(&self, operation: Operation) -> i32 {
+    std::thread::sleep(self.delay);
+    operation.apply(&self.i32_calculator)
+  }
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl<C: I32Calculator> I32Calculator for DelayCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.delayed(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.delayed(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.delayed(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.delayed(Operation::Divide(x, y))
+  }
+}
+
+/// Wraps a saturating [`I32Calculator`](trait.I32Calculator.html) and counts how many of its results
+/// were saturated, e.g. for monitoring.
+///
+/// A result counts as saturated when it differs from the result computed with `i32`'s checked
+/// arithmetic, see [`fold_constant`](enum.Operation.html#method.fold_constant), including when
+/// that overflows or divides by zero.
+pub struct SaturationTrackingCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  saturation_count: core::cell::Cell<usize>,
+}
+
+impl<C: I32Calculator> SaturationTrackingCalculator<C> {
+  /// Returns a calculator that counts the saturated results of `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    SaturationTrackingCalculator {
+      i32_calculator,
+      saturation_count: core::cell::Cell::new(0),
+    }
+  }
+
+  /// Returns how many results were saturated.
+  pub fn saturation_count(&self) -> usize {
+    self.saturation_count.get()
+  }
+
+  fn track(&self, operation: Operation) -> i32 {
+    let result = operation.apply(&self.i32_calculator);
+    if operation.fold_constant() != Some(result) {
+      self.saturation_count.set(self.saturation_count.get() + 1);
+    }
+    result
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for SaturationTrackingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.track(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.track(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.track(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.track(Operation::Divide(x, y))
+  }
+}
+
+/// Wraps an [`I32Calculator`](trait.I32Calculator.html) and records how long each call takes, for
+/// latency analysis.
+///
+/// Every method that calls the wrapped calculator is forwarded and timed as the kind of its main
+/// operation, so e.g. [`checked_modulo`](trait.I32Calculator.html#method.checked_modulo) and
+/// [`divmod`](trait.I32Calculator.html#method.divmod) count as divisions and
+/// [`multiply_add`](trait.I32Calculator.html#method.multiply_add) as a multiplication.
+#[cfg(feature = "std")]
+pub struct TimingCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  samples: core::cell::RefCell<[Vec<std::time::Duration>; OpKind::COUNT]>,
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> TimingCalculator<C> {
+  /// Returns a calculator that times the calls to `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    TimingCalculator {
+      i32_calculator,
+      samples: Default::default(),
+    }
+  }
+
+  /// Returns the `p`-th percentile, from `0.0` to `100.0`, of the durations of operations of kind
+  /// `op`, using the nearest-rank method.
+  ///
+  /// Returns `None` if no operations of kind `op` were timed or `p` is out of range.
+  pub fn percentile(&self, op: OpKind, p: f64) -> Option<std::time::Duration> {
+    if !(0.0..=100.0).contains(&p) {
+      return None;
+    }
+    let mut samples = self.samples.borrow()[op.index()].clone();
+    samples.sort_unstable();
+    let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+    samples.get(rank.saturating_sub(1)).copied()
+  }
+
+  fn timed(&self, operation: Operation) -> i32 {
+    self.time(operation.kind(), |calculator| operation.apply(calculator))
+  }
+
+  fn time<T>(&self, op: OpKind, call: impl FnOnce(&C) -> T) -> T {
+    let start = Instant::now();
+    let result = call(&self.i32_calculator);
+    self.samples.borrow_mut()[op.index()].push(start.elapsed());
+    result
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator> I32Calculator for TimingCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.timed(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.timed(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.timed(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.timed(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Add, |calculator| calculator.checked_add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Subtract, |calculator| {
+      calculator.checked_subtract(x, y)
+    })
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Multiply, |calculator| {
+      calculator.checked_multiply(x, y)
+    })
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Divide, |calculator| calculator.checked_divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Divide, |calculator| calculator.checked_modulo(x, y))
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.time(OpKind::Divide, |calculator| calculator.divmod(x, y))
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.time(OpKind::Multiply, |calculator| {
+      calculator.multiply_add(a, b, c)
+    })
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.time(OpKind::Add, |calculator| calculator.add_i64(x, y))
+  }
+}
+
+/// [`I32Calculator`](trait.I32Calculator.html) that remembers the results of the most recently used
+/// operations, so repeating one doesn't call the wrapped calculator again.
+///
+/// Once `capacity` results are cached, caching another evicts the least recently used one. Lookups
+/// are linear in `capacity`, so this is meant for small caches.
+#[cfg(feature = "alloc")]
+pub struct LruCachingCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  capacity: usize,
+  // least recently used first
+  entries: core::cell::RefCell<Vec<(Operation, i32)>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> LruCachingCalculator<C> {
+  /// Returns a calculator that caches up to `capacity` results of `i32_calculator`.
+  pub fn new(i32_calculator: C, capacity: usize) -> Self {
+    LruCachingCalculator {
+      i32_calculator,
+      capacity,
+      entries: core::cell::RefCell::new(Vec::with_capacity(capacity)),
+    }
+  }
+
+  /// Returns the number of cached results.
+  pub fn len(&self) -> usize {
+    self.entries.borrow().len()
+  }
+
+  /// Returns whether no results are cached.
+  pub fn is_empty(&self) -> bool {
+    self.entries.borrow().is_empty()
+  }
+
+  fn cached(&self, operation: Operation) -> i32 {
+    let mut entries = self.entries.borrow_mut();
+    if let Some(index) = entries.iter().position(|&(cached, _)| cached == operation) {
+      let entry = entries.remove(index);
+      entries.push(entry);
+      return entry.1;
+    }
+    drop(entries);
+
+    let result = operation.apply(&self.i32_calculator);
+    let mut entries = self.entries.borrow_mut();
+    if self.capacity > 0 {
+      if entries.len() == self.capacity {
+        entries.remove(0);
+      }
+      entries.push((operation, result));
+    }
+    result
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> I32Calculator for LruCachingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.cached(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.cached(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.cached(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.cached(Operation::Divide(x, y))
+  }
+}
+
+/// Table of operation implementations provided by a plugin, e.g. one written in C.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct PluginVTable {
+  pub add: extern "C" fn(i32, i32) -> i32,
+  pub subtract: extern "C" fn(i32, i32) -> i32,
+  pub multiply: extern "C" fn(i32, i32) -> i32,
+  pub divide: extern "C" fn(i32, i32) -> i32,
+}
+
+/// [`I32Calculator`](trait.I32Calculator.html) whose operations are implemented by a plugin's
+/// [`PluginVTable`](struct.PluginVTable.html), so implementations can be registered at runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct PluginCalculator {
+  vtable: PluginVTable,
+}
+
+impl PluginCalculator {
+  /// Returns a calculator that calls the functions in `vtable`.
+  pub fn new(vtable: PluginVTable) -> Self {
+    PluginCalculator { vtable }
+  }
+}
+
+impl I32Calculator for PluginCalculator {
+  #[cfg(feature = "alloc")]
+  fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+    Some(Box::new(*self))
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    (self.vtable.add)(x, y)
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    (self.vtable.subtract)(x, y)
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    (self.vtable.multiply)(x, y)
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    (self.vtable.divide)(x, y)
+  }
+}
+
+/// Operands below this are looked up by [`TableCalculator`](struct.TableCalculator.html).
+const TABLE_SIZE: usize = 16;
+
+// results for add, subtract, multiply and divide, indexed by [x][y]; dividing by zero is left to the
+// wrapped calculator, so its entries are unused
+const OPERATION_TABLE: [[[i32; TABLE_SIZE]; TABLE_SIZE]; OpKind::COUNT] = {
+  let mut table = [[[0; TABLE_SIZE]; TABLE_SIZE]; OpKind::COUNT];
+  let mut x = 0;
+  while x < TABLE_SIZE {
+    let mut y = 0;
+    while y < TABLE_SIZE {
+      let (a, b) = (x as i32, y as i32);
+      table[OpKind::Add.index()][x][y] = a + b;
+      table[OpKind::Subtract.index()][x][y] = a - b;
+      table[OpKind::Multiply.index()][x][y] = a * b;
+      if b != 0 {
+        table[OpKind::Divide.index()][x][y] = a / b;
+      }
+      y += 1;
+    }
+    x += 1;
+  }
+  table
+};
+
+/// [`I32Calculator`](trait.I32Calculator.html) that looks up results for operands in `0..16` in a
+/// table computed at compile time, and only calls the wrapped calculator for other operands.
+///
+/// Dividing by zero always goes to the wrapped calculator.
+pub struct TableCalculator<C: I32Calculator> {
+  i32_calculator: C,
+}
+
+impl<C: I32Calculator> TableCalculator<C> {
+  /// Returns a calculator that falls back to `i32_calculator` outside of the table.
+  pub fn new(i32_calculator: C) -> Self {
+    TableCalculator { i32_calculator }
+  }
+
+  fn lookup(&self, operation: Operation) -> i32 {
+    let (x, y) = operation.operands();
+    if let Operation::Divide(_, 0) = operation {
+      return operation.apply(&self.i32_calculator);
+    }
+    let table = &OPERATION_TABLE[operation.kind().index()];
+    match (usize::try_from(x), usize::try_from(y)) {
+      (Ok(x), Ok(y)) if x < TABLE_SIZE && y < TABLE_SIZE => table[x][y],
+      _ => operation.apply(&self.i32_calculator),
+    }
+  }
+}
+
+impl<C: I32Calculator> I32Calculator for TableCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.lookup(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.lookup(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.lookup(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.lookup(Operation::Divide(x, y))
+  }
+}
+
+/// A single [`I32Calculator`](trait.I32Calculator.html) call along with its operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+  /// `x + y`
+  Add(i32, i32),
+  /// `x - y`
+  Subtract(i32, i32),
+  /// `x * y`
+  Multiply(i32, i32),
+  /// `x / y`
+  Divide(i32, i32),
+}
+
+impl Operation {
+  /// Returns the result of performing this operation with `calculator`.
+  pub fn apply<C: I32Calculator + ?Sized>(self, calculator: &C) -> i32 {
+    match self {
+      Operation::Add(x, y) => calculator.add(x, y),
+      Operation::Subtract(x, y) => calculator.subtract(x, y),
+      Operation::Multiply(x, y) => calculator.multiply(x, y),
+      Operation::Divide(x, y) => calculator.divide(x, y),
+    }
+  }
+
+  /// Returns the result of performing this operation with `calculator`'s checked methods.
+  pub fn apply_checked<C: I32Calculator + ?Sized>(
+    self,
+    calculator: &C,
+  ) -> Result<i32, CalculatorError> {
+    match self {
+      Operation::Add(x, y) => calculator.checked_add(x, y),
+      Operation::Subtract(x, y) => calculator.checked_subtract(x, y),
+      Operation::Multiply(x, y) => calculator.checked_multiply(x, y),
+      Operation::Divide(x, y) => calculator.checked_divide(x, y),
+    }
+  }
+
+  /// Returns a future that performs this operation with `calculator`.
+  #[cfg(feature = "async")]
+  pub fn apply_async<C: AsyncI32Calculator + ?Sized>(
+    self,
+    calculator: &C,
+  ) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    match self {
+      Operation::Add(x, y) => calculator.add(x, y),
+      Operation::Subtract(x, y) => calculator.subtract(x, y),
+      Operation::Multiply(x, y) => calculator.multiply(x, y),
+      Operation::Divide(x, y) => calculator.divide(x, y),
+    }
+  }
+
+  /// Returns which kind of operation this is.
+  pub fn kind(&self) -> OpKind {
+    match self {
+      Operation::Add(..) => OpKind::Add,
+      Operation::Subtract(..) => OpKind::Subtract,
+      Operation::Multiply(..) => OpKind::Multiply,
+      Operation::Divide(..) => OpKind::Divide,
+    }
+  }
+
+  /// Returns the left and right operands.
+  pub fn operands(&self) -> (i32, i32) {
+    match *self {
+      Operation::Add(x, y)
+      | Operation::Subtract(x, y)
+      | Operation::Multiply(x, y)
+      | Operation::Divide(x, y) => (x, y),
+    }
+  }
+
+  /// Returns the result of this operation computed directly instead of by a calculator, so an
+  /// optimizer can precompute it, or `None` if it divides by zero or overflows.
+  pub fn fold_constant(&self) -> Option<i32> {
+    match *self {
+      Operation::Add(x, y) => x.checked_add(y),
+      Operation::Subtract(x, y) => x.checked_sub(y),
+      Operation::Multiply(x, y) => x.checked_mul(y),
+      Operation::Divide(x, y) => x.checked_div(y),
+    }
+  }
+
+  /// Returns whether this operation and `other` produce the same results with `calc` when their left
+  /// operands are replaced by each of `samples`, like in a pipeline.
+  ///
+  /// This only checks `samples`, so it can't prove that two operations are equivalent in general.
+  pub fn equivalent_to(
+    &self,
+    other: &Operation,
+    calc: &dyn I32Calculator,
+    samples: &[i32],
+  ) -> bool {
+    samples
+      .iter()
+      .all(|&sample| self.with_left(sample).apply(calc) == other.with_left(sample).apply(calc))
+  }
+
+  /// Returns this operation with its left operand replaced by `left`.
+  pub fn with_left(self, left: i32) -> Operation {
+    match self {
+      Operation::Add(_, y) => Operation::Add(left, y),
+      Operation::Subtract(_, y) => Operation::Subtract(left, y),
+      Operation::Multiply(_, y) => Operation::Multiply(left, y),
+      Operation::Divide(_, y) => Operation::Divide(left, y),
+    }
+  }
+
+  /// Returns whether swapping the operands leaves the result unchanged.
+  pub fn is_commutative(&self) -> bool {
+    matches!(self, Operation::Add(..) | Operation::Multiply(..))
+  }
+
+  /// Returns the relative cost of performing this operation, for scheduling cheap operations first.
+  pub fn cost(&self) -> u32 {
+    match self {
+      Operation::Add(..) | Operation::Subtract(..) => 1,
+      Operation::Multiply(..) => 3,
+      Operation::Divide(..) => 5,
+    }
+  }
+
+  /// Returns an iterator that yields this operation `steps` times.
+  ///
+  /// Fed through a pipeline such as [`fold_ops`](fn.fold_ops.html), this repeats the operation on
+  /// the running result, e.g. `Multiply(x, 2).unfold(n)` doubles `n` times.
+  pub fn unfold(self, steps: u32) -> impl Iterator<Item = Operation> {
+    core::iter::repeat_n(self, steps as usize)
+  }
+
+  /// Attaches `tag` to this operation so its result can be traced.
+  ///
+  /// A `&'static str` tag is borrowed rather than copied to the heap.
+  #[cfg(feature = "alloc")]
+  pub fn tagged(self, tag: impl Into<Cow<'static, str>>) -> TaggedOperation {
+    TaggedOperation {
+      operation: self,
+      tag: Some(tag.into()),
+    }
+  }
+}
+
+impl core::fmt::Display for Operation {
+  /// Formats the operation as an infix expression, e.g. `2 + 3`.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let (x, y) = self.operands();
+    write!(f, "{x} {} {y}", self.kind().symbol())
+  }
+}
+
+/// The kind of an [`Operation`](enum.Operation.html), without its operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpKind {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+}
+
+impl OpKind {
+  /// The number of kinds, i.e. the length of arrays indexed by [`index`](#method.index).
+  pub const COUNT: usize = 4;
+
+  /// Returns the position of this kind in `Add`, `Subtract`, `Multiply`, `Divide` order, for
+  /// indexing arrays of length [`COUNT`](#associatedconstant.COUNT).
+  pub const fn index(self) -> usize {
+    match self {
+      OpKind::Add => 0,
+      OpKind::Subtract => 1,
+      OpKind::Multiply => 2,
+      OpKind::Divide => 3,
+    }
+  }
+
+  /// Returns the right operand that leaves the left operand unchanged, i.e. `0` for addition and
+  /// subtraction and `1` for multiplication and division.
+  pub fn identity(self) -> i32 {
+    match self {
+      OpKind::Add | OpKind::Subtract => 0,
+      OpKind::Multiply | OpKind::Divide => 1,
+    }
+  }
+
+  /// Returns the infix operator for this kind, i.e. `+`, `-`, `*` or `/`.
+  pub fn symbol(self) -> char {
+    match self {
+      OpKind::Add => '+',
+      OpKind::Subtract => '-',
+      OpKind::Multiply => '*',
+      OpKind::Divide => '/',
+    }
+  }
+
+  /// Returns an operation of this kind on `x` and `y`.
+  pub fn operation(self, x: i32, y: i32) -> Operation {
+    match self {
+      OpKind::Add => Operation::Add(x, y),
+      OpKind::Subtract => Operation::Subtract(x, y),
+      OpKind::Multiply => Operation::Multiply(x, y),
+      OpKind::Divide => Operation::Divide(x, y),
+    }
+  }
+}
+
+/// Returns the names of the optional crate features this build was compiled with, for support and
+/// debugging.
+#[cfg(feature = "alloc")]
+pub fn enabled_features() -> Vec<&'static str> {
+  [
+    ("alloc", cfg!(feature = "alloc")),
+    ("std", cfg!(feature = "std")),
+    ("async", cfg!(feature = "async")),
+    ("serde", cfg!(feature = "serde")),
+    ("real-impl", cfg!(feature = "real-impl")),
+    ("proptest", cfg!(feature = "proptest")),
+    ("rayon", cfg!(feature = "rayon")),
+  ]
+  .into_iter()
+  .filter(|&(_, enabled)| enabled)
+  .map(|(name, _)| name)
+  .collect()
+}
+
+/// Returns the result of feeding `start` through `ops` with `calc`.
+///
+/// Each operation's left operand is replaced by the result of the previous operation, starting
+/// with `start`, so only the right operands of `ops` are used.
+pub fn fold_ops<C: I32Calculator + ?Sized>(calc: &C, start: i32, ops: &[Operation]) -> i32 {
+  ops.iter().fold(start, |output, operation| {
+    operation.with_left(output).apply(calc)
+  })
+}
+
+/// A stored pipeline of [`Operation`](enum.Operation.html)s that can be run against any number of
+/// inputs.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+  ops: Vec<Operation>,
+}
+
+#[cfg(feature = "alloc")]
+impl Program {
+  /// Returns a program that performs `ops` in order.
+  pub fn new(ops: Vec<Operation>) -> Self {
+    Program { ops }
+  }
+
+  /// Returns the result of feeding `input` through the program. See [`fold_ops`](fn.fold_ops.html).
+  pub fn run(&self, calc: &dyn I32Calculator, input: i32) -> i32 {
+    fold_ops(calc, input, &self.ops)
+  }
+}
+
+/// State of a [`TypedPipeline`](struct.TypedPipeline.html) without any operations.
+#[cfg(feature = "alloc")]
+pub struct EmptyPipeline;
+
+/// State of a [`TypedPipeline`](struct.TypedPipeline.html) with at least one operation.
+#[cfg(feature = "alloc")]
+pub struct NonEmptyPipeline;
+
+/// Like [`Program`](struct.Program.html), but only has a [`run`](#method.run) method once an
+/// operation has been added, so an empty pipeline can't be run by accident.
+///
+/// ```compile_fail
+/// use rust_mock::{EmptyPipeline, IdentityCalculator, TypedPipeline};
+///
+/// let pipeline: TypedPipeline<EmptyPipeline> = TypedPipeline::new();
+/// pipeline.run(&IdentityCalculator, 1);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedPipeline<State = EmptyPipeline> {
+  ops: Vec<Operation>,
+  state: core::marker::PhantomData<State>,
+}
+
+#[cfg(feature = "alloc")]
+impl TypedPipeline {
+  /// Returns a pipeline without any operations.
+  pub fn new() -> Self {
+    TypedPipeline {
+      ops: Vec::new(),
+      state: core::marker::PhantomData,
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for TypedPipeline {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<State> TypedPipeline<State> {
+  /// Returns the pipeline with `operation` performed after the operations so far.
+  pub fn then(mut self, operation: Operation) -> TypedPipeline<NonEmptyPipeline> {
+    self.ops.push(operation);
+    TypedPipeline {
+      ops: self.ops,
+      state: core::marker::PhantomData,
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl TypedPipeline<NonEmptyPipeline> {
+  /// Returns the result of feeding `input` through the pipeline. See [`fold_ops`](fn.fold_ops.html).
+  pub fn run(&self, calc: &dyn I32Calculator, input: i32) -> i32 {
+    fold_ops(calc, input, &self.ops)
+  }
+}
+
+/// One [`Operation`](enum.Operation.html) of a [`CompiledPipeline`](struct.CompiledPipeline.html),
+/// taking the calculator and the value so far.
+#[cfg(feature = "alloc")]
+pub type CompiledStep = Box<dyn Fn(&dyn I32Calculator, i32) -> i32>;
+
+/// A pipeline of [`Operation`](enum.Operation.html)s turned into a closure per operation by
+/// [`compile_pipeline`](fn.compile_pipeline.html).
+#[cfg(feature = "alloc")]
+pub struct CompiledPipeline {
+  steps: Vec<CompiledStep>,
+}
+
+#[cfg(feature = "alloc")]
+impl CompiledPipeline {
+  /// Returns the result of feeding `input` through the pipeline. See [`fold_ops`](fn.fold_ops.html).
+  pub fn run(&self, calc: &dyn I32Calculator, input: i32) -> i32 {
+    self
+      .steps
+      .iter()
+      .fold(input, |value, step| step(calc, value))
+  }
+}
+
+/// Compiles `ops` into closures that can be run on many inputs without matching on `ops` again.
+#[cfg(feature = "alloc")]
+pub fn compile_pipeline(ops: &[Operation]) -> CompiledPipeline {
+  let steps = ops
+    .iter()
+    .map(|&operation| -> CompiledStep {
+      match operation {
+        Operation::Add(_, y) => Box::new(move |calc, value| calc.add(value, y)),
+        Operation::Subtract(_, y) => Box::new(move |calc, value| calc.subtract(value, y)),
+        Operation::Multiply(_, y) => Box::new(move |calc, value| calc.multiply(value, y)),
+        Operation::Divide(_, y) => Box::new(move |calc, value| calc.divide(value, y)),
+      }
+    })
+    .collect();
+  CompiledPipeline { steps }
+}
+
+/// A running value that is updated in place by a calculator.
+///
+/// The `try_*` methods return the accumulator so they can be chained with `?`. When one fails the
+/// value is left as it was before that call.
+pub struct Accumulator<'a> {
+  i32_calculator: &'a dyn I32Calculator,
+  value: i32,
+}
+
+impl<'a> Accumulator<'a> {
+  /// Returns an accumulator starting at `value` that uses `i32_calculator`.
+  pub fn new(i32_calculator: &'a dyn I32Calculator, value: i32) -> Self {
+    Accumulator {
+      i32_calculator,
+      value,
+    }
+  }
+
+  /// Returns the current value.
+  pub fn value(&self) -> i32 {
+    self.value
+  }
+
+  /// Adds `n` to the value. See [`checked_add`](trait.I32Calculator.html#method.checked_add).
+  pub fn try_add(&mut self, n: i32) -> Result<&mut Self, CalculatorError> {
+    self.value = self.i32_calculator.checked_add(self.value, n)?;
+    Ok(self)
+  }
+
+  /// Subtracts `n` from the value. See
+  /// [`checked_subtract`](trait.I32Calculator.html#method.checked_subtract).
+  pub fn try_subtract(&mut self, n: i32) -> Result<&mut Self, CalculatorError> {
+    self.value = self.i32_calculator.checked_subtract(self.value, n)?;
+    Ok(self)
+  }
+
+  /// Multiplies the value by `n`. See
+  /// [`checked_multiply`](trait.I32Calculator.html#method.checked_multiply).
+  pub fn try_multiply(&mut self, n: i32) -> Result<&mut Self, CalculatorError> {
+    self.value = self.i32_calculator.checked_multiply(self.value, n)?;
+    Ok(self)
+  }
+
+  /// Divides the value by `n`. See
+  /// [`checked_divide`](trait.I32Calculator.html#method.checked_divide).
+  pub fn try_divide(&mut self, n: i32) -> Result<&mut Self, CalculatorError> {
+    self.value = self.i32_calculator.checked_divide(self.value, n)?;
+    Ok(self)
+  }
+}
+
+/// Count, sum, minimum and maximum of the values seen so far, with the sum kept by a calculator.
+///
+/// Unlike a decorator this doesn't hold a calculator, so each call takes the one to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunningStats {
+  count: usize,
+  sum: i32,
+  min: Option<i32>,
+  max: Option<i32>,
+}
+
+impl RunningStats {
+  /// Returns statistics of no values.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds `x` to the statistics, using `calc`'s `add` for the sum.
+  pub fn observe(&mut self, x: i32, calc: &dyn I32Calculator) {
+    self.count += 1;
+    self.sum = calc.add(self.sum, x);
+    self.min = Some(self.min.map_or(x, |min| min.min(x)));
+    self.max = Some(self.max.map_or(x, |max| max.max(x)));
+  }
+
+  /// Returns the number of values observed.
+  pub fn count(&self) -> usize {
+    self.count
+  }
+
+  /// Returns the sum of the values observed.
+  pub fn sum(&self) -> i32 {
+    self.sum
+  }
+
+  /// Returns the smallest value observed, if any.
+  pub fn min(&self) -> Option<i32> {
+    self.min
+  }
+
+  /// Returns the largest value observed, if any.
+  pub fn max(&self) -> Option<i32> {
+    self.max
+  }
+
+  /// Returns the mean of the values observed using `calc`'s `divide`, or `None` if there are none
+  /// or more than `i32::MAX`.
+  pub fn mean(&self, calc: &dyn I32Calculator) -> Option<i32> {
+    match i32::try_from(self.count) {
+      Ok(0) | Err(_) => None,
+      Ok(count) => Some(calc.divide(self.sum, count)),
+    }
+  }
+}
+
+/// The greatest common divisor of the values seen so far, computed by a calculator's
+/// [`gcd`](trait.I32Calculator.html#method.gcd).
+///
+/// Like [`RunningStats`](struct.RunningStats.html), each call takes the calculator to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GcdAccumulator {
+  current: i32,
+}
+
+impl GcdAccumulator {
+  /// Returns the accumulator of no values, whose GCD is `0`.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Includes `x` in the GCD, using `calc`'s `gcd`.
+  pub fn push(&mut self, x: i32, calc: &dyn I32Calculator) {
+    self.current = calc.gcd(self.current, x);
+  }
+
+  /// Returns the GCD of the values pushed so far.
+  pub fn current(&self) -> i32 {
+    self.current
+  }
+}
+
+/// A value that routes the arithmetic operators through a calculator, so `value * 2 + 1` calls
+/// the calculator's `multiply` and then its `add`.
+///
+/// Unlike [`Accumulator`](struct.Accumulator.html), the plain, unchecked calculator methods are
+/// used.
+#[derive(Clone, Copy)]
+pub struct CalcValue<'a> {
+  i32_calculator: &'a dyn I32Calculator,
+  value: i32,
+}
+
+impl<'a> CalcValue<'a> {
+  /// Returns `value`, whose operators use `i32_calculator`.
+  pub fn new(i32_calculator: &'a dyn I32Calculator, value: i32) -> Self {
+    CalcValue {
+      i32_calculator,
+      value,
+    }
+  }
+
+  /// Returns the value.
+  pub fn value(&self) -> i32 {
+    self.value
+  }
+
+  fn with(self, operation: Operation) -> Self {
+    CalcValue {
+      value: operation.apply(self.i32_calculator),
+      ..self
+    }
+  }
+}
+
+impl<'a> core::ops::Add<i32> for CalcValue<'a> {
+  type Output = CalcValue<'a>;
+
+  fn add(self, rhs: i32) -> CalcValue<'a> {
+    self.with(Operation::Add(self.value, rhs))
+  }
+}
+
+impl<'a> core::ops::Sub<i32> for CalcValue<'a> {
+  type Output = CalcValue<'a>;
+
+  fn sub(self, rhs: i32) -> CalcValue<'a> {
+    self.with(Operation::Subtract(self.value, rhs))
+  }
+}
+
+impl<'a> core::ops::Mul<i32> for CalcValue<'a> {
+  type Output = CalcValue<'a>;
+
+  fn mul(self, rhs: i32) -> CalcValue<'a> {
+    self.with(Operation::Multiply(self.value, rhs))
+  }
+}
+
+impl<'a> core::ops::Div<i32> for CalcValue<'a> {
+  type Output = CalcValue<'a>;
+
+  fn div(self, rhs: i32) -> CalcValue<'a> {
+    self.with(Operation::Divide(self.value, rhs))
+  }
+}
+
+/// A record of [`Operation`](enum.Operation.html)s and their results, e.g. for a session.
+///
+/// With the `serde` and `std` features it can be saved to and loaded from a JSON file.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationLog {
+  entries: Vec<(Operation, i32)>,
+}
+
+#[cfg(feature = "alloc")]
+impl OperationLog {
+  /// Returns an empty log.
+  pub fn new() -> Self {
+    OperationLog::default()
+  }
+
+  /// Records that `operation` returned `result`.
+  pub fn append(&mut self, operation: Operation, result: i32) {
+    self.entries.push((operation, result));
+  }
+
+  /// Returns every operation and its result, oldest first.
+  pub fn entries(&self) -> &[(Operation, i32)] {
+    &self.entries
+  }
+
+  /// Returns the number of recorded operations.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if no operations have been recorded.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Writes the log to `path` as JSON, replacing any existing file.
+  #[cfg(all(feature = "serde", feature = "std"))]
+  pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer(&mut writer, self)?;
+    // dropping the writer would silently discard an error from writing what's still buffered
+    writer.flush()
+  }
+
+  /// Reads a log that was written by [`save`](#method.save).
+  #[cfg(all(feature = "serde", feature = "std"))]
+  pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    Ok(serde_json::from_reader(file)?)
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for OperationLog {
+  /// Formats one `operation = result` line per entry, oldest first.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    for (operation, result) in &self.entries {
+      writeln!(f, "{operation} = {result}")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl core::ops::Index<usize> for OperationLog {
+  type Output = (Operation, i32);
+
+  /// Returns the `index`th recorded operation and its result, oldest first.
+  fn index(&self, index: usize) -> &Self::Output {
+    &self.entries[index]
+  }
+}
+
+/// A calculator that records every successful operation passed to another calculator in an
+/// [`OperationLog`](struct.OperationLog.html).
+///
+/// Only [`Operation`](enum.Operation.html)s can be recorded, so the other methods, e.g.
+/// [`divmod`](trait.I32Calculator.html#method.divmod), are forwarded to the wrapped calculator
+/// without being recorded.
+///
+/// The log is behind a [`RefCell`](https://doc.rust-lang.org/core/cell/struct.RefCell.html), so
+/// entries are indexed through [`log`](#method.log), e.g. `recorder.log()[2]`.
+#[cfg(feature = "alloc")]
+pub struct RecordingCalculator<C: I32Calculator> {
+  i32_calculator: C,
+  log: core::cell::RefCell<OperationLog>,
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> RecordingCalculator<C> {
+  /// Returns a calculator that records the operations passed to `i32_calculator`.
+  pub fn new(i32_calculator: C) -> Self {
+    RecordingCalculator {
+      i32_calculator,
+      log: core::cell::RefCell::new(OperationLog::new()),
+    }
+  }
+
+  /// Returns the operations recorded so far.
+  pub fn log(&self) -> core::cell::Ref<'_, OperationLog> {
+    self.log.borrow()
+  }
+
+  /// Returns the recorded operations, consuming the calculator.
+  pub fn into_log(self) -> OperationLog {
+    self.log.into_inner()
+  }
+
+  /// Returns the number of recorded operations.
+  pub fn len(&self) -> usize {
+    self.log.borrow().len()
+  }
+
+  /// Returns `true` if no operations have been recorded.
+  pub fn is_empty(&self) -> bool {
+    self.log.borrow().is_empty()
+  }
+
+  fn apply(&self, operation: Operation) -> i32 {
+    let result = operation.apply(&self.i32_calculator);
+    self.log.borrow_mut().append(operation, result);
+    result
+  }
+
+  fn apply_checked(&self, operation: Operation) -> Result<i32, CalculatorError> {
+    let result = operation.apply_checked(&self.i32_calculator)?;
+    self.log.borrow_mut().append(operation, result);
+    Ok(result)
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> I32Calculator for RecordingCalculator<C> {
+  fn supports(&self, op_kind: OpKind) -> bool {
+    self.i32_calculator.supports(op_kind)
+  }
+
+  fn backend_name(&self) -> &'static str {
+    self.i32_calculator.backend_name()
+  }
+
+  fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+    self.i32_calculator.validate(x, y)
+  }
+
+  fn add(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    self.apply(Operation::Divide(x, y))
+  }
+
+  fn checked_add(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Add(x, y))
+  }
+
+  fn checked_subtract(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Subtract(x, y))
+  }
+
+  fn checked_multiply(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Multiply(x, y))
+  }
+
+  fn checked_divide(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.apply_checked(Operation::Divide(x, y))
+  }
+
+  fn checked_modulo(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    self.i32_calculator.checked_modulo(x, y)
+  }
+
+  fn divmod(&self, x: i32, y: i32) -> Result<(i32, i32), CalculatorError> {
+    self.i32_calculator.divmod(x, y)
+  }
+
+  fn multiply_add(&self, a: i32, b: i32, c: i32) -> Result<i32, CalculatorError> {
+    self.i32_calculator.multiply_add(a, b, c)
+  }
+
+  fn add_i64(&self, x: i64, y: i64) -> i64 {
+    self.i32_calculator.add_i64(x, y)
+  }
+}
+
+/// Returns `operations` without the ones that leave their left operand unchanged, i.e. adding or
+/// subtracting `0` and multiplying or dividing by `1`.
+///
+/// When `operations` is a pipeline that feeds each result into the next operation's left operand,
+/// the simplified pipeline produces the same result.
+#[cfg(feature = "alloc")]
+pub fn simplify(operations: &[Operation]) -> Vec<Operation> {
+  operations
+    .iter()
+    .filter(|operation| {
+      !matches!(
+        operation,
+        Operation::Add(_, 0)
+          | Operation::Subtract(_, 0)
+          | Operation::Multiply(_, 1)
+          | Operation::Divide(_, 1)
+      )
+    })
+    .copied()
+    .collect()
+}
+
+/// Returns `operations` with each run of consecutive additions or multiplications merged into one,
+/// e.g. adding `2` and then `3` becomes adding `5`.
+///
+/// Like [`simplify`](fn.simplify.html), this is meant for pipelines that feed each result into the
+/// next operation's left operand, so the left operand of every merged operation but the first is
+/// ignored.
+///
+/// Operations are only merged when combining their right operands doesn't overflow and the
+/// intermediate result can't overflow unless the final one does, so a checked calculator reports
+/// the same overflows: additions are merged unless their right operands have opposite signs, and
+/// multiplications only when the second right operand is positive.
+#[cfg(feature = "alloc")]
+pub fn coalesce(operations: &[Operation]) -> Vec<Operation> {
+  let mut coalesced: Vec<Operation> = Vec::with_capacity(operations.len());
+  for &operation in operations {
+    let merged = match (coalesced.last(), operation) {
+      // `x + a` lies between `x` and `x + a + b` when `a` and `b` don't have opposite signs
+      (Some(&Operation::Add(x, a)), Operation::Add(_, b)) if a.signum() * b.signum() >= 0 => {
+        a.checked_add(b).map(|y| Operation::Add(x, y))
+      }
+      // `|x * a| <= |x * a * b|` with the same sign when `b` is positive
+      (Some(&Operation::Multiply(x, a)), Operation::Multiply(_, b)) if b > 0 => {
+        a.checked_mul(b).map(|y| Operation::Multiply(x, y))
+      }
+      _ => None,
+    };
+    match merged {
+      Some(merged) => *coalesced.last_mut().unwrap() = merged,
+      None => coalesced.push(operation),
+    }
+  }
+  coalesced
+}
+
+/// An [`Operation`](enum.Operation.html) that optionally carries a tag, e.g. a correlation id.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaggedOperation {
+  pub operation: Operation,
+  pub tag: Option<Cow<'static, str>>,
+}
+
+#[cfg(feature = "alloc")]
+impl From<Operation> for TaggedOperation {
+  fn from(operation: Operation) -> Self {
+    TaggedOperation {
+      operation,
+      tag: None,
+    }
+  }
+}
+
+/// The result of a [`TaggedOperation`](struct.TaggedOperation.html) along with its tag.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaggedResult {
+  pub result: i32,
+  pub tag: Option<Cow<'static, str>>,
+}
+
+/// Result of [`divide_exact`](trait.CalculatorExt.html#method.divide_exact).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DivResult {
+  pub quotient: i32,
+  /// Whether the remainder is zero.
+  pub exact: bool,
+}
+
+/// An algebraic law that a calculator broke, along with the operands that showed it, see
+/// [`verify_algebraic_laws`](trait.CalculatorExt.html#method.verify_algebraic_laws).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LawViolation {
+  /// `x + y` and `y + x` differ.
+  AddCommutativity { x: i32, y: i32 },
+  /// `(x + y) + z` and `x + (y + z)` differ.
+  AddAssociativity { x: i32, y: i32, z: i32 },
+  /// `x * y` and `y * x` differ.
+  MultiplyCommutativity { x: i32, y: i32 },
+  /// `(x * y) * z` and `x * (y * z)` differ.
+  MultiplyAssociativity { x: i32, y: i32, z: i32 },
+}
+
+impl core::fmt::Display for LawViolation {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      LawViolation::AddCommutativity { x, y } => {
+        write!(f, "{x} + {y} differs from {y} + {x}")
+      }
+      LawViolation::AddAssociativity { x, y, z } => {
+        write!(f, "({x} + {y}) + {z} differs from {x} + ({y} + {z})")
+      }
+      LawViolation::MultiplyCommutativity { x, y } => {
+        write!(f, "{x} * {y} differs from {y} * {x}")
+      }
+      LawViolation::MultiplyAssociativity { x, y, z } => {
+        write!(f, "({x} * {y}) * {z} differs from {x} * ({y} * {z})")
+      }
+    }
+  }
+}
+
+impl core::error::Error for LawViolation {}
+
+/// Helpers built on top of [`I32Calculator`](trait.I32Calculator.html) that every calculator gets.
+///
+/// These live outside of [`I32Calculator`](trait.I32Calculator.html) so that `automock` doesn't
+/// replace them, meaning that mocks only need expectations for the underlying calls.
+pub trait CalculatorExt: I32Calculator {
+  /// Returns the result of each of `operations`, in order.
+  #[cfg(feature = "alloc")]
+  fn batch(&self, operations: &[Operation]) -> Vec<i32> {
+    operations
+      .iter()
+      .map(|operation| operation.apply(self))
+      .collect()
+  }
+
+  /// Like [`batch`](#method.batch), but performs each distinct operation only once, so repeated
+  /// operations don't call the calculator again.
+  #[cfg(feature = "std")]
+  fn batch_dedup(&self, operations: &[Operation]) -> Vec<i32> {
+    let mut results = std::collections::HashMap::new();
+    operations
+      .iter()
+      .map(|&operation| {
+        *results
+          .entry(operation)
+          .or_insert_with(|| operation.apply(self))
+      })
+      .collect()
+  }
+
+  /// Returns the result of each of `operations`, in order, without allocating.
+  fn batch_array<const N: usize>(&self, operations: [Operation; N]) -> [i32; N] {
+    operations.map(|operation| operation.apply(self))
+  }
+
+  /// Returns the result of each of `operations`, in order, along with its tag.
+  #[cfg(feature = "alloc")]
+  fn batch_tagged(&self, operations: &[TaggedOperation]) -> Vec<TaggedResult> {
+    operations
+      .iter()
+      .map(|tagged| TaggedResult {
+        result: tagged.operation.apply(self),
+        tag: tagged.tag.clone(),
+      })
+      .collect()
+  }
+
+  /// [`checked_add`](trait.I32Calculator.html#method.checked_add) as a
+  /// [`ControlFlow`](core::ops::ControlFlow), for use with `try_fold` and friends.
+  fn checked_add_cf(&self, x: i32, y: i32) -> core::ops::ControlFlow<CalculatorError, i32> {
+    match self.checked_add(x, y) {
+      Ok(sum) => core::ops::ControlFlow::Continue(sum),
+      Err(error) => core::ops::ControlFlow::Break(error),
+    }
+  }
+
+  /// Yields the running sum of `values` using
+  /// [`checked_add`](trait.I32Calculator.html#method.checked_add), ending after the first error.
+  fn checked_running_sum<'a>(
+    &'a self,
+    values: &'a [i32],
+  ) -> impl Iterator<Item = Result<i32, CalculatorError>> + 'a {
+    values.iter().scan(Some(0), move |sum, &value| {
+      let result = self.checked_add((*sum)?, value);
+      *sum = result.as_ref().ok().copied();
+      Some(result)
+    })
+  }
+
+  /// Returns `x / y` rounded to the nearest integer, with halves rounded to the even neighbour
+  /// (banker's rounding), e.g. `5 / 2` is `2` and `7 / 2` is `4`.
+  ///
+  /// The truncated quotient and remainder come from
+  /// [`divmod`](trait.I32Calculator.html#method.divmod), so this fails like it does.
+  fn divide_bankers(&self, x: i32, y: i32) -> Result<i32, CalculatorError> {
+    let (quotient, remainder) = self.divmod(x, y)?;
+    let twice_remainder = 2 * u64::from(remainder.unsigned_abs());
+    let divisor = u64::from(y.unsigned_abs());
+    let round_away = twice_remainder > divisor || (twice_remainder == divisor && quotient % 2 != 0);
+    if !round_away {
+      return Ok(quotient);
+    }
+    // |quotient| < |x| here since |y| >= 2, so stepping away from zero can't overflow
+    Ok(if (x < 0) == (y < 0) {
+      quotient + 1
+    } else {
+      quotient - 1
+    })
+  }
+
+  /// Checks that addition and multiplication are commutative and associative for each `(x, y, z)`
+  /// of `samples`, returning the first law that is broken.
+  ///
+  /// For each sample the laws are checked in the order of the
+  /// [`LawViolation`](enum.LawViolation.html) variants, with commutativity checked on `x` and `y`.
+  fn verify_algebraic_laws(&self, samples: &[(i32, i32, i32)]) -> Result<(), LawViolation> {
+    for &(x, y, z) in samples {
+      if self.add(x, y) != self.add(y, x) {
+        return Err(LawViolation::AddCommutativity { x, y });
+      }
+      if self.add(self.add(x, y), z) != self.add(x, self.add(y, z)) {
+        return Err(LawViolation::AddAssociativity { x, y, z });
+      }
+      if self.multiply(x, y) != self.multiply(y, x) {
+        return Err(LawViolation::MultiplyCommutativity { x, y });
+      }
+      if self.multiply(self.multiply(x, y), z) != self.multiply(x, self.multiply(y, z)) {
+        return Err(LawViolation::MultiplyAssociativity { x, y, z });
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the quotient of `x` and `y` along with whether the division was exact, using
+  /// [`divmod`](trait.I32Calculator.html#method.divmod).
+  fn divide_exact(&self, x: i32, y: i32) -> Result<DivResult, CalculatorError> {
+    let (quotient, remainder) = self.divmod(x, y)?;
+    Ok(DivResult {
+      quotient,
+      exact: remainder == 0,
+    })
+  }
+}
+
+impl<C: I32Calculator + ?Sized> CalculatorExt for C {}
+
+/// An arithmetic expression tree that can be evaluated by an
+/// [`Application`](struct.Application.html).
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+  /// A literal value.
+  Literal(i32),
+  /// The sum of both expressions.
+  Add(Box<Expr>, Box<Expr>),
+  /// The difference of both expressions.
+  Subtract(Box<Expr>, Box<Expr>),
+  /// The product of both expressions.
+  Multiply(Box<Expr>, Box<Expr>),
+  /// The quotient of both expressions.
+  Divide(Box<Expr>, Box<Expr>),
+}
+
+#[cfg(feature = "alloc")]
+impl From<Operation> for Expr {
+  /// Returns the expression that applies `operation` to its two literal operands.
+  fn from(operation: Operation) -> Self {
+    let (x, y) = operation.operands();
+    let (x, y) = (Box::new(Expr::Literal(x)), Box::new(Expr::Literal(y)));
+    match operation.kind() {
+      OpKind::Add => Expr::Add(x, y),
+      OpKind::Subtract => Expr::Subtract(x, y),
+      OpKind::Multiply => Expr::Multiply(x, y),
+      OpKind::Divide => Expr::Divide(x, y),
+    }
+  }
+}
+
+/// Error returned when converting an [`Expr`](enum.Expr.html) that isn't a single operation on two
+/// literals into an [`Operation`](enum.Operation.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotAnOperation;
+
+impl core::fmt::Display for NotAnOperation {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "expression is not a single operation on two literals")
+  }
+}
+
+impl core::error::Error for NotAnOperation {}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<&Expr> for Operation {
+  type Error = NotAnOperation;
+
+  fn try_from(expr: &Expr) -> Result<Self, Self::Error> {
+    let (op, x, y) = match expr {
+      Expr::Literal(_) => return Err(NotAnOperation),
+      Expr::Add(x, y) => (OpKind::Add, x, y),
+      Expr::Subtract(x, y) => (OpKind::Subtract, x, y),
+      Expr::Multiply(x, y) => (OpKind::Multiply, x, y),
+      Expr::Divide(x, y) => (OpKind::Divide, x, y),
+    };
+    match (&**x, &**y) {
+      (&Expr::Literal(x), &Expr::Literal(y)) => Ok(op.operation(x, y)),
+      _ => Err(NotAnOperation),
+    }
+  }
+}
+
+/// Index of a node in an [`ExprArena`](struct.ExprArena.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExprId(usize);
+
+/// A node of an [`ExprArena`](struct.ExprArena.html), like [`Expr`](enum.Expr.html) but with its
+/// children referred to by id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExprNode {
+  /// A literal value.
+  Literal(i32),
+  /// The sum of both nodes.
+  Add(ExprId, ExprId),
+  /// The difference of both nodes.
+  Subtract(ExprId, ExprId),
+  /// The product of both nodes.
+  Multiply(ExprId, ExprId),
+  /// The quotient of both nodes.
+  Divide(ExprId, ExprId),
+}
+
+/// Stores the nodes of arithmetic expression trees in a single `Vec`, so building a large tree
+/// doesn't need an allocation per node like [`Expr`](enum.Expr.html) does.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExprArena {
+  nodes: Vec<ExprNode>,
+}
+
+#[cfg(feature = "alloc")]
+impl ExprArena {
+  /// Returns an empty arena.
+  pub fn new() -> Self {
+    ExprArena::default()
+  }
+
+  /// Adds `node` to the arena, returning its id.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `node` refers to an id that isn't in the arena yet, which also rules out cycles.
+  pub fn push(&mut self, node: ExprNode) -> ExprId {
+    if let ExprNode::Add(x, y)
+    | ExprNode::Subtract(x, y)
+    | ExprNode::Multiply(x, y)
+    | ExprNode::Divide(x, y) = node
+    {
+      assert!(
+        x.0 < self.nodes.len() && y.0 < self.nodes.len(),
+        "expression nodes can only refer to nodes already in the arena"
+      );
+    }
+    self.nodes.push(node);
+    ExprId(self.nodes.len() - 1)
+  }
+
+  /// Returns the node with id `id`.
+  pub fn get(&self, id: ExprId) -> ExprNode {
+    self.nodes[id.0]
+  }
+}
+
+/// The real roots of a quadratic equation, see
+/// [`Application::solve_quadratic`](struct.Application.html#method.solve_quadratic).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuadraticRoots {
+  /// The equation has no real roots.
+  NoRealRoots,
+  /// The equation has a single, repeated root.
+  OneRoot(i32),
+  /// The equation has two distinct roots, smallest first.
+  TwoRoots(i32, i32),
+  /// The equation has real roots, but not all of them are integers.
+  NonIntegralRoots,
+  /// `a` is zero, so the equation is linear rather than quadratic.
+  NotQuadratic,
+}
+
+/// Mockable trait that a client for an external 8-bit service would implement, e.g. on a small
+/// microcontroller.
+///
+/// Since `u8` overflows so easily, the default methods saturate at `0` and `u8::MAX` instead.
+#[cfg_attr(all(test, feature = "std"), mockall::automock)]
+pub trait U8Calculator {
+  /// Returns the sum of `x` and `y`, saturating at `u8::MAX`.
+  fn add(&self, x: u8, y: u8) -> u8 {
+    x.saturating_add(y)
+  }
+  /// Returns the difference of `x` and `y`, saturating at `0`.
+  fn subtract(&self, x: u8, y: u8) -> u8 {
+    x.saturating_sub(y)
+  }
+  /// Returns the product of `x` and `y`, saturating at `u8::MAX`.
+  fn multiply(&self, x: u8, y: u8) -> u8 {
+    x.saturating_mul(y)
+  }
+  /// Returns the quotient of `x` and `y`, which can't overflow. Panics if `y` is zero.
+  fn divide(&self, x: u8, y: u8) -> u8 {
+    x / y
+  }
+}
+
+/// Toy client implementation of [`U8Calculator`](trait.U8Calculator.html) that uses its
+/// saturating defaults.
+pub struct ExternalU8Calculator;
+
+impl U8Calculator for ExternalU8Calculator {}
+
+/// Mockable trait that a client for an external floating point service would implement.
+#[cfg_attr(all(test, feature = "std"), mockall::automock)]
+pub trait F64Calculator {
+  /// Returns the sum of `x` and `y`.
+  fn add(&self, x: f64, y: f64) -> f64;
+  /// Returns the difference of `x` and `y`.
+  fn subtract(&self, x: f64, y: f64) -> f64;
+  /// Returns the product of `x` and `y`.
+  fn multiply(&self, x: f64, y: f64) -> f64;
+  /// Returns the quotient of `x` and `y`.
+  fn divide(&self, x: f64, y: f64) -> f64;
+}
+
+/// Adapter that makes an [`F64Calculator`](trait.F64Calculator.html) usable as an
+/// [`I32Calculator`](trait.I32Calculator.html).
+///
+/// Arguments are converted to `f64` exactly. Results are rounded to the nearest integer with ties
+/// rounded away from zero (see [`f64::round`]); results outside the range of `i32` saturate at
+/// `i32::MIN`/`i32::MAX` and `NaN` becomes `0`.
+#[cfg(feature = "std")]
+pub struct F64AsI32<C: F64Calculator>(pub C);
+
+#[cfg(feature = "std")]
+impl<C: F64Calculator> F64AsI32<C> {
+  fn round(value: f64) -> i32 {
+    // `as` saturates out-of-range values and maps NaN to 0.
+    value.round() as i32
+  }
+}
+
+#[cfg(feature = "std")]
+impl<C: F64Calculator + 'static> I32Calculator for F64AsI32<C> {
+  fn add(&self, x: i32, y: i32) -> i32 {
+    Self::round(self.0.add(x.into(), y.into()))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> i32 {
+    Self::round(self.0.subtract(x.into(), y.into()))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> i32 {
+    Self::round(self.0.multiply(x.into(), y.into()))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> i32 {
+    Self::round(self.0.divide(x.into(), y.into()))
+  }
+}
+
+/// How far an [`F64Calculator`](trait.F64Calculator.html) result strays from an
+/// [`I32Calculator`](trait.I32Calculator.html) result for the same operation, as reported by
+/// [`cross_validate`](fn.cross_validate.html).
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrecisionReport {
+  /// The operation that was computed.
+  pub operation: Operation,
+  /// The integer calculator's result.
+  pub int_result: i32,
+  /// The float calculator's result, before rounding.
+  pub float_result: f64,
+  /// The absolute difference between `int_result` and `float_result` rounded to the nearest
+  /// integer.
+  pub difference: u64,
+}
+
+/// Computes every operation on each of `cases` with both `int_calc` and `float_calc` and reports
+/// how far apart the results are.
+///
+/// Float results are rounded to the nearest integer with ties rounded away from zero, so a
+/// nonzero [`difference`](struct.PrecisionReport.html#structfield.difference) shows where integer
+/// truncation diverges from float arithmetic. Reports are returned case by case, in the order
+/// add, subtract, multiply and divide.
+///
+/// # Panics
+///
+/// Panics if `int_calc` panics, e.g. on a case with a zero right operand.
+#[cfg(feature = "std")]
+pub fn cross_validate(
+  int_calc: &dyn I32Calculator,
+  float_calc: &dyn F64Calculator,
+  cases: &[(i32, i32)],
+) -> Vec<PrecisionReport> {
+  let kinds = [
+    OpKind::Add,
+    OpKind::Subtract,
+    OpKind::Multiply,
+    OpKind::Divide,
+  ];
+  cases
+    .iter()
+    .flat_map(|&(x, y)| kinds.into_iter().map(move |kind| kind.operation(x, y)))
+    .map(|operation| {
+      let int_result = operation.apply(int_calc);
+      let (x, y) = operation.operands();
+      let (x, y) = (f64::from(x), f64::from(y));
+      let float_result = match operation.kind() {
+        OpKind::Add => float_calc.add(x, y),
+        OpKind::Subtract => float_calc.subtract(x, y),
+        OpKind::Multiply => float_calc.multiply(x, y),
+        OpKind::Divide => float_calc.divide(x, y),
+      };
+      // `as` saturates out-of-range values and maps NaN to 0.
+      let rounded = float_result.round() as i64;
+      PrecisionReport {
+        operation,
+        int_result,
+        float_result,
+        difference: (i64::from(int_result) - rounded).unsigned_abs(),
+      }
+    })
+    .collect()
+}
+
+/// Mockable trait that a client for an asynchronous external service would implement.
+///
+/// Methods return boxed futures so that the trait can be mocked and used as a trait object. The
+/// futures don't depend on any particular runtime, so they can be driven by any executor.
+#[cfg(feature = "async")]
+#[cfg_attr(all(test, feature = "std"), mockall::automock)]
+pub trait AsyncI32Calculator {
+  /// Returns the sum of `x` and `y`.
+  fn add(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>>;
+  /// Returns the difference of `x` and `y`.
+  fn subtract(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>>;
+  /// Returns the product of `x` and `y`.
+  fn multiply(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>>;
+  /// Returns the quotient of `x` and `y`.
+  fn divide(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>>;
+}
+
+/// Wraps an [`AsyncI32Calculator`](trait.AsyncI32Calculator.html) and retries operations that fail
+/// with [`CalculatorError::Transient`](enum.CalculatorError.html#variant.Transient) with
+/// exponential backoff.
+///
+/// After the `n`th failed attempt it sleeps for `base_delay * 2^(n - 1)`, saturating at
+/// `Duration::MAX`, before trying again, until `max_attempts` attempts have been made. At least one
+/// attempt is always made. Other errors are deterministic, so they are returned as they are without
+/// retrying. When an operation was retried and every attempt failed, the last error is returned
+/// wrapped in [`CalculatorError::Underlying`](enum.CalculatorError.html#variant.Underlying).
+#[cfg(feature = "async")]
+pub struct AsyncRetryingCalculator<C: AsyncI32Calculator + Sync> {
+  async_i32_calculator: C,
+  base_delay: std::time::Duration,
+  max_attempts: u32,
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> AsyncRetryingCalculator<C> {
+  /// Returns a calculator that retries `async_i32_calculator` up to `max_attempts` times in total.
+  pub fn new(async_i32_calculator: C, base_delay: std::time::Duration, max_attempts: u32) -> Self {
+    AsyncRetryingCalculator {
+      async_i32_calculator,
+      base_delay,
+      max_attempts,
+    }
+  }
+
+  fn retry(&self, operation: Operation) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    Box::pin(async move {
+      let mut delay = self.base_delay;
+      let mut attempt = 1;
+      loop {
+        match operation.apply_async(&self.async_i32_calculator).await {
+          Err(CalculatorError::Transient) if attempt < self.max_attempts => {
+            tokio::time::sleep(delay).await;
+            delay = delay.saturating_mul(2);
+            attempt += 1;
+          }
+          Err(error) if attempt > 1 => return Err(CalculatorError::Underlying(Box::new(error))),
+          result => return result,
+        }
+      }
+    })
+  }
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> AsyncI32Calculator for AsyncRetryingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.retry(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.retry(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.retry(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.retry(Operation::Divide(x, y))
+  }
+}
+
+/// An operation recorded by [`AsyncRecordingCalculator`](struct.AsyncRecordingCalculator.html),
+/// along with its result.
+#[cfg(feature = "async")]
+pub type OperationEvent = (Operation, Result<i32, CalculatorError>);
+
+/// Wraps an [`AsyncI32Calculator`](trait.AsyncI32Calculator.html) and sends every operation and its
+/// result on a channel as it completes, so another task can process them live.
+///
+/// Events are dropped once the receiver is.
+#[cfg(feature = "async")]
+pub struct AsyncRecordingCalculator<C: AsyncI32Calculator + Sync> {
+  async_i32_calculator: C,
+  events: tokio::sync::mpsc::UnboundedSender<OperationEvent>,
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> AsyncRecordingCalculator<C> {
+  /// Returns a calculator that records the operations of `async_i32_calculator`, along with the
+  /// receiver for its events.
+  pub fn new(
+    async_i32_calculator: C,
+  ) -> (Self, tokio::sync::mpsc::UnboundedReceiver<OperationEvent>) {
+    let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let calculator = AsyncRecordingCalculator {
+      async_i32_calculator,
+      events,
+    };
+    (calculator, receiver)
+  }
+
+  fn record(&self, operation: Operation) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    Box::pin(async move {
+      let result = operation.apply_async(&self.async_i32_calculator).await;
+      // nobody is listening anymore, which is fine
+      let _ = self.events.send((operation, result.clone()));
+      result
+    })
+  }
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> AsyncI32Calculator for AsyncRecordingCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.record(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.record(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.record(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.record(Operation::Divide(x, y))
+  }
+}
+
+/// Wraps an [`AsyncI32Calculator`](trait.AsyncI32Calculator.html) and abandons its operations once
+/// a [`CancellationToken`](tokio_util::sync::CancellationToken) is cancelled, e.g. on shutdown.
+///
+/// Operations that haven't completed by then return
+/// [`CalculatorError::Cancelled`](enum.CalculatorError.html#variant.Cancelled), and operations
+/// started afterwards don't call the wrapped calculator at all.
+#[cfg(feature = "async")]
+pub struct CancellableCalculator<C: AsyncI32Calculator + Sync> {
+  async_i32_calculator: C,
+  token: tokio_util::sync::CancellationToken,
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> CancellableCalculator<C> {
+  /// Returns a calculator whose calls to `async_i32_calculator` are cancelled along with `token`.
+  pub fn new(async_i32_calculator: C, token: tokio_util::sync::CancellationToken) -> Self {
+    CancellableCalculator {
+      async_i32_calculator,
+      token,
+    }
+  }
+
+  fn cancellable(&self, operation: Operation) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    Box::pin(async move {
+      if self.token.is_cancelled() {
+        return Err(CalculatorError::Cancelled);
+      }
+      self
+        .token
+        .run_until_cancelled(operation.apply_async(&self.async_i32_calculator))
+        .await
+        .unwrap_or(Err(CalculatorError::Cancelled))
+    })
+  }
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncI32Calculator + Sync> AsyncI32Calculator for CancellableCalculator<C> {
+  fn add(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.cancellable(Operation::Add(x, y))
+  }
+
+  fn subtract(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.cancellable(Operation::Subtract(x, y))
+  }
+
+  fn multiply(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.cancellable(Operation::Multiply(x, y))
+  }
+
+  fn divide(&self, x: i32, y: i32) -> BoxFuture<'_, Result<i32, CalculatorError>> {
+    self.cancellable(Operation::Divide(x, y))
+  }
+}
+
+/// Like [`Application`](struct.Application.html), but for an
+/// [`AsyncI32Calculator`](trait.AsyncI32Calculator.html).
+#[cfg(feature = "async")]
+pub struct AsyncApplication {
+  pub async_i32_calculator: Box<dyn AsyncI32Calculator + Send + Sync>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncApplication {
+  /// [`cool_algorithm`](struct.Application.html#method.cool_algorithm) using the asynchronous
+  /// calculator, returning the first error instead of a result.
+  pub async fn cool_algorithm_async(&self, x: i32) -> Result<i32, CalculatorError> {
+    let calculator = &self.async_i32_calculator;
+
+    let mut output = x;
+    output = calculator.add(output, 0).await?;
+    output = calculator.subtract(output, 0).await?;
+    output = calculator.multiply(output, 1).await?;
+    output = calculator.divide(output, 1).await?;
+
+    Ok(output)
+  }
+
+  /// Returns a stream of the results of [`cool_algorithm_async`](#method.cool_algorithm_async) on
+  /// each of `inputs`, in order.
+  ///
+  /// Each input is only pulled from `inputs` once the previous result has been taken, so a slow
+  /// consumer slows down the producer rather than building up a queue.
+  pub fn process_stream<'a, S: futures::Stream<Item = i32> + 'a>(
+    &'a self,
+    inputs: S,
+  ) -> impl futures::Stream<Item = Result<i32, CalculatorError>> + 'a {
+    use futures::StreamExt;
+
+    inputs.then(move |x| self.cool_algorithm_async(x))
+  }
+}
+
+/// The right operands of each step of
+/// [`cool_algorithm_with`](struct.Application.html#method.cool_algorithm_with).
+///
+/// The default is `0`, `0`, `1` and `1`, which leaves the input unchanged like
+/// [`cool_algorithm`](struct.Application.html#method.cool_algorithm).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CoolParams {
+  pub add: i32,
+  pub subtract: i32,
+  pub multiply: i32,
+  pub divide: i32,
+}
+
+impl Default for CoolParams {
+  fn default() -> Self {
+    CoolParams {
+      add: 0,
+      subtract: 0,
+      multiply: 1,
+      divide: 1,
+    }
+  }
+}
+
+/// Named calculators, for applications with several backends.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+pub struct CalculatorRegistry {
+  calculators: alloc::collections::BTreeMap<String, Box<dyn I32Calculator>>,
+}
+
+#[cfg(feature = "alloc")]
+impl CalculatorRegistry {
+  /// Returns an empty registry.
+  pub fn new() -> Self {
+    CalculatorRegistry::default()
+  }
+
+  /// Registers `calculator` under `id`, returning the calculator it replaces, if any.
+  pub fn register(
+    &mut self,
+    id: impl Into<String>,
+    calculator: Box<dyn I32Calculator>,
+  ) -> Option<Box<dyn I32Calculator>> {
+    self.calculators.insert(id.into(), calculator)
+  }
+
+  /// Returns the calculator registered under `id`.
+  pub fn get(&self, id: &str) -> Option<&dyn I32Calculator> {
+    self.calculators.get(id).map(|calculator| &**calculator)
+  }
+
+  /// Unregisters and returns the calculator registered under `id`.
+  pub fn remove(&mut self, id: &str) -> Option<Box<dyn I32Calculator>> {
+    self.calculators.remove(id)
+  }
+
+  /// Reads a TOML file with a [`CalculatorConfig`](struct.CalculatorConfig.html) for each
+  /// calculator, see [`from_toml_str`](#method.from_toml_str).
+  #[cfg(all(feature = "serde", feature = "std"))]
+  pub fn from_toml(path: &std::path::Path) -> std::io::Result<Self> {
+    Self::from_toml_str(&std::fs::read_to_string(path)?)
+  }
+
+  /// Parses a [`CalculatorConfig`](struct.CalculatorConfig.html) for each calculator from TOML and
+  /// registers the calculators under their table names:
+  ///
+  /// ```toml
+  /// [calculators.clamped]
+  /// backend = "identity"
+  /// overflow_policy = "saturate"
+  /// bounds = [-100, 100]
+  /// ```
+  ///
+  /// Invalid TOML is reported as [`InvalidData`](std::io::ErrorKind::InvalidData) and invalid
+  /// bounds as [`InvalidInput`](std::io::ErrorKind::InvalidInput).
+  #[cfg(all(feature = "serde", feature = "std"))]
+  pub fn from_toml_str(toml: &str) -> std::io::Result<Self> {
+    #[derive(serde::Deserialize)]
+    struct RegistryConfig {
+      calculators: alloc::collections::BTreeMap<String, CalculatorConfig>,
+    }
+
+    let config: RegistryConfig = toml::from_str(toml)
+      .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    let mut registry = CalculatorRegistry::new();
+    for (id, calculator) in config.calculators {
+      let calculator = calculator
+        .build()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+      registry.register(id, calculator);
+    }
+    Ok(registry)
+  }
+}
+
+/// A step of an [`Application::plan`](struct.Application.html#method.plan).
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlanStep {
+  /// The position of the step, starting from `0`.
+  pub index: usize,
+  /// The operation, with its left operand replaced by the previous prediction if there is one.
+  pub operation: Operation,
+  /// The predicted result, or `None` if it can't be computed without the calculator.
+  pub predicted: Option<i32>,
+}
+
+/// Receives the results of an [`Application`](struct.Application.html), see
+/// [`Application::subscribe`](struct.Application.html#method.subscribe).
+///
+/// Observers must be `Send + Sync` so that an application with observers can still be shared
+/// between threads.
+#[cfg_attr(all(test, feature = "std"), mockall::automock)]
+pub trait ResultObserver: Send + Sync {
+  /// Called after `cool_algorithm(x)` returned `result`.
+  fn notify(&self, x: i32, result: i32);
+}
+
+/// struct that has an [`I32Calculator`](trait.I32Calculator.html)
+/// [`i32_calculator`](struct.Application.html#structfield.i32_calculator) field.
+///
+/// The calculator is a `Box<dyn I32Calculator>` by default, but `Application<C>` holds a
+/// `Box<C>` so calls can be dispatched statically when the calculator type is known.
+///
+/// This needs the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub struct Application<C: I32Calculator + ?Sized = dyn I32Calculator> {
+  pub i32_calculator: Box<C>,
+  overflow_guard: bool,
+  frac_bits: u32,
+  #[cfg(feature = "std")]
+  observers: std::sync::Mutex<Vec<alloc::sync::Weak<dyn ResultObserver>>>,
+}
+
+/// Error returned by [`Application::rolling_apply`](struct.Application.html#method.rolling_apply)
+/// when asked for windows of zero values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmptyWindow;
+
+impl core::fmt::Display for EmptyWindow {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "a rolling window needs at least one value")
+  }
+}
+
+impl core::error::Error for EmptyWindow {}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator + ?Sized> Application<C> {
+  /// Returns an application that uses `i32_calculator`, with the overflow guard disabled.
+  pub fn new(i32_calculator: Box<C>) -> Self {
+    Application {
+      i32_calculator,
+      overflow_guard: false,
+      frac_bits: 0,
+      #[cfg(feature = "std")]
+      observers: std::sync::Mutex::new(Vec::new()),
+    }
+  }
+
+  /// Enables or disables the overflow guard used by
+  /// [`guarded_cool_algorithm`](#method.guarded_cool_algorithm).
+  pub fn with_overflow_guard(mut self, enabled: bool) -> Self {
+    self.overflow_guard = enabled;
+    self
+  }
+
+  /// Switches [`scaled_pipeline`](#method.scaled_pipeline) to fixed-point values with `frac_bits`
+  /// fractional bits, e.g. `16` for Q16. The default of `0` is plain integers.
+  pub fn scaled(mut self, frac_bits: u32) -> Self {
+    self.frac_bits = frac_bits;
+    self
+  }
+
+  /// Notifies `observer` of every result of [`cool_algorithm`](#method.cool_algorithm),
+  /// [`cool_algorithm_traced`](#method.cool_algorithm_traced) and
+  /// [`checked_cool_algorithm`](#method.checked_cool_algorithm), and so of the methods built on
+  /// them, for as long as it is alive. Failed checked runs aren't reported. Observers that have
+  /// been dropped are forgotten at the next notification.
+  ///
+  /// This needs the `std` feature.
+  #[cfg(feature = "std")]
+  pub fn subscribe(&mut self, observer: alloc::sync::Weak<dyn ResultObserver>) {
+    self.observers_mut().push(observer);
+  }
+
+  /// Returns the number of observers, including dropped ones that haven't been forgotten yet.
+  ///
+  /// This needs the `std` feature.
+  #[cfg(feature = "std")]
+  pub fn observer_count(&self) -> usize {
+    self.observers().len()
+  }
+
+  #[cfg(feature = "std")]
+  fn observers(&self) -> std::sync::MutexGuard<'_, Vec<alloc::sync::Weak<dyn ResultObserver>>> {
+    // the lock is never held while user code runs, so a poisoned list is still consistent
+    self
+      .observers
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+  }
+
+  #[cfg(feature = "std")]
+  fn observers_mut(&mut self) -> &mut Vec<alloc::sync::Weak<dyn ResultObserver>> {
+    self
+      .observers
+      .get_mut()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+  }
+
+  #[cfg(feature = "std")]
+  fn notify(&self, x: i32, result: i32) {
+    let observers: Vec<_> = {
+      let mut observers = self.observers();
+      observers.retain(|observer| observer.strong_count() > 0);
+      observers
+        .iter()
+        .filter_map(alloc::sync::Weak::upgrade)
+        .collect()
+    };
+    // notified after releasing the lock, so observers may use the application
+    for observer in observers {
+      observer.notify(x, result);
+    }
+  }
+
+  #[cfg(not(feature = "std"))]
+  fn notify(&self, _x: i32, _result: i32) {}
+
+  /// Returns a wrapper that remembers the result of [`cool_algorithm`](#method.cool_algorithm) for
+  /// each input, see [`MemoizedApplication`](struct.MemoizedApplication.html).
+  #[cfg(feature = "std")]
+  pub fn with_memoization(self) -> MemoizedApplication<C> {
+    MemoizedApplication {
+      application: self,
+      results: core::cell::RefCell::new(std::collections::HashMap::new()),
+    }
+  }
+
+  /// An important bit of application logic that makes use of the `I32Calculator` interface.
+  ///
+  /// This will be unit tested.
+  pub fn cool_algorithm(&self, x: i32) -> i32 {
+    let mut output = x;
+
+    output = self.i32_calculator.add(output, 0);
+    output = self.i32_calculator.subtract(output, 0);
+    output = self.i32_calculator.multiply(output, 1);
+    output = self.i32_calculator.divide(output, 1);
+
+    self.notify(x, output);
+    output
+  }
+
+  /// [`cool_algorithm`](#method.cool_algorithm) with every operand and intermediate result passed
+  /// through [`black_box`](core::hint::black_box), so benchmarks can't have the calls optimized
+  /// away.
+  pub fn cool_algorithm_bench(&self, x: i32) -> i32 {
+    use core::hint::black_box;
+
+    let mut output = black_box(x);
+
+    output = black_box(self.i32_calculator.add(output, black_box(0)));
+    output = black_box(self.i32_calculator.subtract(output, black_box(0)));
+    output = black_box(self.i32_calculator.multiply(output, black_box(1)));
+    black_box(self.i32_calculator.divide(output, black_box(1)))
+  }
+
+  /// [`cool_algorithm`](#method.cool_algorithm) that also returns a log of each step it took.
+  pub fn cool_algorithm_traced(&self, x: i32) -> (i32, OperationLog) {
+    let mut trace = OperationLog::new();
+    let mut output = x;
+    for (op, y) in [
+      (OpKind::Add, 0),
+      (OpKind::Subtract, 0),
+      (OpKind::Multiply, 1),
+      (OpKind::Divide, 1),
+    ] {
+      let operation = op.operation(output, y);
+      output = operation.apply(&*self.i32_calculator);
+      trace.append(operation, output);
+    }
+    self.notify(x, output);
+    (output, trace)
+  }
+
+  /// [`checked_cool_algorithm`](#method.checked_cool_algorithm) when the overflow guard is enabled,
+  /// otherwise the unchecked [`cool_algorithm`](#method.cool_algorithm), which never fails.
+  ///
+  /// See [`with_overflow_guard`](#method.with_overflow_guard).
+  pub fn guarded_cool_algorithm(&self, x: i32) -> Result<i32, CalculatorError> {
+    if self.overflow_guard {
+      self.checked_cool_algorithm(x)
+    } else {
+      Ok(self.cool_algorithm(x))
+    }
+  }
+
+  /// [`cool_algorithm`](#method.cool_algorithm) using the calculator's checked methods, returning the
+  /// first error instead of a result.
+  pub fn checked_cool_algorithm(&self, x: i32) -> Result<i32, CalculatorError> {
+    let result = self.cool_algorithm_with(x, CoolParams::default())?;
+    self.notify(x, result);
+    Ok(result)
+  }
+
+  /// [`checked_cool_algorithm`](#method.checked_cool_algorithm) with the operands from `params`
+  /// instead of `0`, `0`, `1` and `1`.
+  pub fn cool_algorithm_with(&self, x: i32, params: CoolParams) -> Result<i32, CalculatorError> {
+    let mut output = x;
+
+    output = self.i32_calculator.checked_add(output, params.add)?;
+    output = self
+      .i32_calculator
+      .checked_subtract(output, params.subtract)?;
+    output = self
+      .i32_calculator
+      .checked_multiply(output, params.multiply)?;
+    output = self.i32_calculator.checked_divide(output, params.divide)?;
+
+    Ok(output)
+  }
+
+  /// Lazily runs [`checked_cool_algorithm`](#method.checked_cool_algorithm) on each of `inputs`, so
+  /// an error for one input doesn't stop the others from being processed.
+  pub fn try_process_stream<'a, I: Iterator<Item = i32> + 'a>(
+    &'a self,
+    inputs: I,
+  ) -> impl Iterator<Item = Result<i32, CalculatorError>> + 'a {
+    inputs.map(|x| self.checked_cool_algorithm(x))
+  }
+
+  /// Returns the result of feeding `x` through `operations`. See [`fold_ops`](fn.fold_ops.html).
+  pub fn pipeline(&self, x: i32, operations: &[Operation]) -> i32 {
+    fold_ops(self.i32_calculator.as_ref(), x, operations)
+  }
+
+  /// Returns the [`backend_name`](trait.I32Calculator.html#method.backend_name) of the calculator.
+  pub fn backend(&self) -> &str {
+    self.i32_calculator.backend_name()
+  }
+
+  /// Returns a copy of the application that uses the calculator's
+  /// [`boxed_clone`](trait.I32Calculator.html#method.boxed_clone), or `None` if the calculator
+  /// can't be copied. Like `clone`, the copy keeps the overflow guard and scale settings and the
+  /// observers.
+  pub fn try_clone(&self) -> Option<Application> {
+    Some(Application {
+      i32_calculator: self.i32_calculator.boxed_clone()?,
+      overflow_guard: self.overflow_guard,
+      frac_bits: self.frac_bits,
+      #[cfg(feature = "std")]
+      observers: std::sync::Mutex::new(self.observers().clone()),
+    })
+  }
+
+  /// Returns the calculator as a `T`, or `None` if it is some other type.
+  pub fn calculator_as<T: I32Calculator>(&self) -> Option<&T> {
+    // deref first so that the box itself isn't the one being downcast
+    (*self.i32_calculator).as_any().downcast_ref()
+  }
+
+  /// Returns the value of `expr`, evaluating the left side of each node before its right side.
+  pub fn evaluate(&self, expr: &Expr) -> i32 {
+    match expr {
+      Expr::Literal(value) => *value,
+      Expr::Add(x, y) => self.i32_calculator.add(self.evaluate(x), self.evaluate(y)),
+      Expr::Subtract(x, y) => self
+        .i32_calculator
+        .subtract(self.evaluate(x), self.evaluate(y)),
+      Expr::Multiply(x, y) => self
+        .i32_calculator
+        .multiply(self.evaluate(x), self.evaluate(y)),
+      Expr::Divide(x, y) => self
+        .i32_calculator
+        .divide(self.evaluate(x), self.evaluate(y)),
+    }
+  }
+
+  /// [`evaluate`](#method.evaluate)s the tree of `arena` rooted at `root`.
+  pub fn evaluate_arena(&self, arena: &ExprArena, root: ExprId) -> i32 {
+    let evaluate = |id| self.evaluate_arena(arena, id);
+    match arena.get(root) {
+      ExprNode::Literal(value) => value,
+      ExprNode::Add(x, y) => self.i32_calculator.add(evaluate(x), evaluate(y)),
+      ExprNode::Subtract(x, y) => self.i32_calculator.subtract(evaluate(x), evaluate(y)),
+      ExprNode::Multiply(x, y) => self.i32_calculator.multiply(evaluate(x), evaluate(y)),
+      ExprNode::Divide(x, y) => self.i32_calculator.divide(evaluate(x), evaluate(y)),
+    }
+  }
+
+  /// Returns the value of `expr` using checked arithmetic, stopping at the first error.
+  pub fn evaluate_checked(&self, expr: &Expr) -> Result<i32, CalculatorError> {
+    match expr {
+      Expr::Literal(value) => Ok(*value),
+      Expr::Add(x, y) => self
+        .i32_calculator
+        .checked_add(self.evaluate_checked(x)?, self.evaluate_checked(y)?),
+      Expr::Subtract(x, y) => self
+        .i32_calculator
+        .checked_subtract(self.evaluate_checked(x)?, self.evaluate_checked(y)?),
+      Expr::Multiply(x, y) => self
+        .i32_calculator
+        .checked_multiply(self.evaluate_checked(x)?, self.evaluate_checked(y)?),
+      Expr::Divide(x, y) => self
+        .i32_calculator
+        .checked_divide(self.evaluate_checked(x)?, self.evaluate_checked(y)?),
+    }
+  }
+
+  /// Combines `values` from left to right with `op`, starting from the first value, e.g.
+  /// `reduce(&[10, 2], OpKind::Subtract)` is `10 - 2`.
+  ///
+  /// Returns `op`'s [`identity`](enum.OpKind.html#method.identity) when `values` is empty.
+  pub fn reduce(&self, values: &[i32], op: OpKind) -> i32 {
+    match values.split_first() {
+      Some((&first, rest)) => self.reduce_with_init(first, rest, op),
+      None => op.identity(),
+    }
+  }
+
+  /// Combines `values` from left to right with `op`, starting from `init`.
+  pub fn reduce_with_init(&self, init: i32, values: &[i32], op: OpKind) -> i32 {
+    values.iter().fold(init, |output, &value| {
+      op.operation(output, value)
+        .apply(self.i32_calculator.as_ref())
+    })
+  }
+
+  /// Like [`reduce`](#method.reduce), but returns the running result after each of `values`, so
+  /// the first entry is the first value itself.
+  pub fn reduce_scanned(&self, values: &[i32], op: OpKind) -> Vec<i32> {
+    let Some((&first, rest)) = values.split_first() else {
+      return Vec::new();
+    };
+    let running = rest.iter().scan(first, |output, &value| {
+      *output = op
+        .operation(*output, value)
+        .apply(self.i32_calculator.as_ref());
+      Some(*output)
+    });
+    core::iter::once(first).chain(running).collect()
+  }
+
+  /// [`reduce`](#method.reduce)s each run of `window` consecutive values with `op`, in order, so
+  /// each window starts from its first value.
+  ///
+  /// Returns an empty vec when `window` is larger than `values`, and fails if `window` is zero.
+  pub fn rolling_apply(
+    &self,
+    values: &[i32],
+    window: usize,
+    op: OpKind,
+  ) -> Result<Vec<i32>, EmptyWindow> {
+    if window == 0 {
+      return Err(EmptyWindow);
+    }
+    Ok(
+      values
+        .windows(window)
+        .map(|values| self.reduce(values, op))
+        .collect(),
+    )
+  }
+
+  /// Returns the median of `values`, or `None` if there are none.
+  ///
+  /// For an even number of values the two middle ones are averaged with the calculator, rounding
+  /// toward zero like `/`. Values of the same sign are averaged from their difference so that large
+  /// neighbours such as `[i32::MAX, i32::MAX]` don't overflow.
+  pub fn median(&self, values: &[i32]) -> Option<i32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+      0 => None,
+      len if len % 2 == 1 => Some(sorted[middle]),
+      _ => {
+        let (low, high) = (sorted[middle - 1], sorted[middle]);
+        let calculator = &self.i32_calculator;
+        Some(if low < 0 && high >= 0 {
+          // the sum of values of opposite signs can't overflow
+          calculator.divide(calculator.add(low, high), 2)
+        } else {
+          // nor can the difference of values of the same sign, and halving it from the value
+          // closer to zero rounds toward zero
+          let half_difference = calculator.divide(calculator.subtract(high, low), 2);
+          if low >= 0 {
+            calculator.add(low, half_difference)
+          } else {
+            calculator.subtract(high, half_difference)
+          }
+        })
+      }
+    }
+  }
+
+  /// Returns the sum of `values` accumulated in an `i64`, so it can't overflow `i32`.
+  ///
+  /// The calculator's [`add_i64`](trait.I32Calculator.html#method.add_i64) is called once per
+  /// element with the running total, so the backend is only consulted per element if it overrides
+  /// `add_i64`; the default adds locally.
+  pub fn sum_i64(&self, values: &[i32]) -> i64 {
+    values.iter().fold(0, |total, &value| {
+      self.i32_calculator.add_i64(total, value.into())
+    })
+  }
+
+  /// Returns a rolling checksum of `values` computed by the calculator.
+  ///
+  /// Starting from `17`, each value is folded in as `checksum * 31 + value`, so the result depends
+  /// on the order of `values`. This is only meant for catching accidental changes, not tampering.
+  pub fn checksum(&self, values: &[i32]) -> i32 {
+    values.iter().fold(17, |checksum, &value| {
+      self
+        .i32_calculator
+        .add(self.i32_calculator.multiply(checksum, 31), value)
+    })
+  }
+
+  /// Approximates the square root of `n` with integer Newton iteration, using the calculator's
+  /// [`add`](trait.I32Calculator.html#tymethod.add),
+  /// [`divide`](trait.I32Calculator.html#tymethod.divide) and
+  /// [`multiply`](trait.I32Calculator.html#tymethod.multiply).
+  ///
+  /// Iteration starts from above the root and stops early once the estimate squared is at most `n`,
+  /// at which point it is `⌊√n⌋`. With too few `iterations` the estimate is too large.
+  pub fn approximate_sqrt(&self, n: i32, iterations: usize) -> Result<i32, CalculatorError> {
+    if n < 0 {
+      return Err(CalculatorError::NegativeSquareRoot { radicand: n });
+    }
+    if n == 0 {
+      return Ok(0);
+    }
+
+    // ⌊√i32::MAX⌋, so squaring an estimate never overflows
+    let mut estimate = n.min(46_340);
+    for _ in 0..iterations {
+      if self.i32_calculator.multiply(estimate, estimate) <= n {
+        break;
+      }
+      let sum = self
+        .i32_calculator
+        .add(estimate, self.i32_calculator.divide(n, estimate));
+      estimate = self.i32_calculator.divide(sum, 2);
+    }
+    Ok(estimate)
+  }
+
+  /// Returns the roots of `ax² + bx + c = 0`, computing the discriminant with the calculator's
+  /// checked methods and its square root with [`approximate_sqrt`](#method.approximate_sqrt).
+  ///
+  /// Returns [`NotQuadratic`](enum.QuadraticRoots.html#variant.NotQuadratic) if `a` is zero, and
+  /// fails if `b² - 4ac` or any other intermediate value doesn't fit in `i32`.
+  pub fn solve_quadratic(&self, a: i32, b: i32, c: i32) -> Result<QuadraticRoots, CalculatorError> {
+    if a == 0 {
+      return Ok(QuadraticRoots::NotQuadratic);
+    }
+    let calculator = &self.i32_calculator;
+
+    let b_squared = calculator.checked_multiply(b, b)?;
+    let four_a_c = calculator.checked_multiply(calculator.checked_multiply(4, a)?, c)?;
+    let discriminant = calculator.checked_subtract(b_squared, four_a_c)?;
+    let Ok(root) = self.approximate_sqrt(discriminant, 64) else {
+      return Ok(QuadraticRoots::NoRealRoots);
+    };
+    if calculator.checked_multiply(root, root)? != discriminant {
+      return Ok(QuadraticRoots::NonIntegralRoots);
+    }
+
+    let negative_b = calculator.checked_subtract(0, b)?;
+    let two_a = calculator.checked_multiply(2, a)?;
+    let solve = |numerator: i32| -> Result<Option<i32>, CalculatorError> {
+      let quotient = calculator.checked_divide(numerator, two_a)?;
+      Ok((calculator.checked_multiply(quotient, two_a)? == numerator).then_some(quotient))
+    };
+
+    if discriminant == 0 {
+      return Ok(
+        solve(negative_b)?.map_or(QuadraticRoots::NonIntegralRoots, QuadraticRoots::OneRoot),
+      );
+    }
+    let roots = (
+      solve(calculator.checked_subtract(negative_b, root)?)?,
+      solve(calculator.checked_add(negative_b, root)?)?,
+    );
+    Ok(match roots {
+      (Some(x), Some(y)) => QuadraticRoots::TwoRoots(x.min(y), x.max(y)),
+      _ => QuadraticRoots::NonIntegralRoots,
+    })
+  }
+
+  /// Divides each `(x, y)` of `pairs` with checked division, returning the successful quotients
+  /// and the index of each pair that failed along with its error.
+  pub fn divide_all(&self, pairs: &[(i32, i32)]) -> (Vec<i32>, Vec<(usize, CalculatorError)>) {
+    let mut quotients = Vec::new();
+    let mut errors = Vec::new();
+    for (index, &(x, y)) in pairs.iter().enumerate() {
+      match self.i32_calculator.checked_divide(x, y) {
+        Ok(quotient) => quotients.push(quotient),
+        Err(error) => errors.push((index, error)),
+      }
+    }
+    (quotients, errors)
+  }
+
+  /// Subtracts each of `values` from `start` in turn with checked subtraction, returning the index
+  /// of the value that failed along with its error.
+  pub fn subtract_all(&self, start: i32, values: &[i32]) -> Result<i32, (usize, CalculatorError)> {
+    values
+      .iter()
+      .enumerate()
+      .try_fold(start, |balance, (index, &value)| {
+        self
+          .i32_calculator
+          .checked_subtract(balance, value)
+          .map_err(|error| (index, error))
+      })
+  }
+
+  /// Returns `base` raised to `exp`, or an error if it doesn't fit in `i32`.
+  ///
+  /// The power is computed by repeated squaring with the calculator's
+  /// [`checked_multiply`](trait.I32Calculator.html#method.checked_multiply), so it takes about
+  /// `2 * log2(exp)` calls. No intermediate value exceeds the result, so an overflow is only
+  /// reported when the result itself doesn't fit, and then as an overflow of `"power"`.
+  pub fn checked_power(&self, base: i32, exp: u32) -> Result<i32, CalculatorError> {
+    let multiply = |x, y| {
+      self
+        .i32_calculator
+        .checked_multiply(x, y)
+        .map_err(|error| match error {
+          CalculatorError::Overflow { .. } => CalculatorError::Overflow {
+            op: "power",
+            x: base,
+            y: exp.try_into().unwrap_or(i32::MAX),
+          },
+          error => error,
+        })
+    };
+
+    let (mut power, mut square, mut exp) = (1, base, exp);
+    while exp > 0 {
+      if exp & 1 == 1 {
+        power = multiply(power, square)?;
+      }
+      exp >>= 1;
+      // only square while a higher bit still needs it, so `square` never exceeds the result
+      if exp > 0 {
+        square = multiply(square, square)?;
+      }
+    }
+    Ok(power)
+  }
+  /// Returns the product of the fixed-point numbers `a` and `b`, which both have `frac_bits`
+  /// fractional bits, e.g. `fixed_multiply(1 << 15, 1 << 15, 16)` is `1 << 14` (`0.5 * 0.5 = 0.25`
+  /// in Q16).
+  ///
+  /// The raw product is computed with the calculator's
+  /// [`checked_multiply`](trait.I32Calculator.html#method.checked_multiply) and then shifted right by
+  /// `frac_bits`, rounding toward negative infinity, so it has to fit in `i32` before it is scaled
+  /// back. When it doesn't, or `frac_bits` is 32 or more, this fails with an overflow of
+  /// `"fixed_multiply"`.
+  pub fn fixed_multiply(&self, a: i32, b: i32, frac_bits: u32) -> Result<i32, CalculatorError> {
+    let overflow = || CalculatorError::Overflow {
+      op: "fixed_multiply",
+      x: a,
+      y: b,
+    };
+    if frac_bits >= i32::BITS {
+      return Err(overflow());
+    }
+    let product = self
+      .i32_calculator
+      .checked_multiply(a, b)
+      .map_err(|error| match error {
+        CalculatorError::Overflow { .. } => overflow(),
+        error => error,
+      })?;
+    Ok(product >> frac_bits)
+  }
+
+  /// Returns the quotient of the fixed-point numbers `a` and `b`, which both have `frac_bits`
+  /// fractional bits, e.g. `fixed_divide(1 << 14, 1 << 15, 16)` is `1 << 15` (`0.25 / 0.5 = 0.5` in
+  /// Q16).
+  ///
+  /// `a` is shifted left by `frac_bits` first and the result divided by `b` with the calculator's
+  /// [`checked_divide`](trait.I32Calculator.html#method.checked_divide), rounding toward zero.
+  /// Fails if `b` is zero, and with an overflow of `"fixed_divide"` if the shifted `a` or the
+  /// quotient doesn't fit in `i32` or `frac_bits` is 32 or more.
+  pub fn fixed_divide(&self, a: i32, b: i32, frac_bits: u32) -> Result<i32, CalculatorError> {
+    let overflow = || CalculatorError::Overflow {
+      op: "fixed_divide",
+      x: a,
+      y: b,
+    };
+    if b == 0 {
+      return Err(CalculatorError::DivisionByZero { dividend: a });
+    }
+    if frac_bits >= i32::BITS {
+      return Err(overflow());
+    }
+    let shifted = a << frac_bits;
+    // bits shifted out of `a`, or into its sign bit, don't come back when shifting back
+    if shifted >> frac_bits != a {
+      return Err(overflow());
+    }
+    self
+      .i32_calculator
+      .checked_divide(shifted, b)
+      .map_err(|error| match error {
+        CalculatorError::Overflow { .. } => overflow(),
+        error => error,
+      })
+  }
+
+  /// Returns the result of feeding `x` through `operations` like [`pipeline`](#method.pipeline),
+  /// but with `x`, the right operands and each result being fixed-point values with the fractional
+  /// bits set by [`scaled`](#method.scaled).
+  ///
+  /// Additions and subtractions use the calculator's checked methods directly, and
+  /// multiplications and divisions use [`fixed_multiply`](#method.fixed_multiply) and
+  /// [`fixed_divide`](#method.fixed_divide). Fails with the first error.
+  pub fn scaled_pipeline(&self, x: i32, operations: &[Operation]) -> Result<i32, CalculatorError> {
+    operations.iter().try_fold(x, |output, operation| {
+      let (_, y) = operation.operands();
+      match operation.kind() {
+        OpKind::Add => self.i32_calculator.checked_add(output, y),
+        OpKind::Subtract => self.i32_calculator.checked_subtract(output, y),
+        OpKind::Multiply => self.fixed_multiply(output, y, self.frac_bits),
+        OpKind::Divide => self.fixed_divide(output, y, self.frac_bits),
+      }
+    })
+  }
+
+  /// Returns the steps that [`fold_ops`](fn.fold_ops.html) would take to feed `start` through
+  /// `ops`, with each result predicted by
+  /// [`fold_constant`](enum.Operation.html#method.fold_constant) instead of the calculator.
+  ///
+  /// Once a step overflows or divides by zero, it and the following steps have no prediction, and
+  /// the following steps keep their operations as given.
+  pub fn plan(&self, start: i32, ops: &[Operation]) -> Vec<PlanStep> {
+    let mut output = Some(start);
+    ops
+      .iter()
+      .enumerate()
+      .map(|(index, &operation)| {
+        let operation = output.map_or(operation, |x| operation.with_left(x));
+        output = output.and(operation.fold_constant());
+        PlanStep {
+          index,
+          operation,
+          predicted: output,
+        }
+      })
+      .collect()
+  }
+
+  /// Returns the [`plan`](#method.plan) for feeding `start` through `ops` as one `step n: x + y`
+  /// line per operation, with the left operands that couldn't be predicted shown as `?`.
+  pub fn describe_plan(&self, start: i32, ops: &[Operation]) -> String {
+    let mut plan = String::new();
+    let mut predicted = true;
+    for step in self.plan(start, ops) {
+      if step.index > 0 {
+        plan.push('\n');
+      }
+      let line = if predicted {
+        alloc::format!("step {}: {}", step.index + 1, step.operation)
+      } else {
+        alloc::format!(
+          "step {}: ? {} {}",
+          step.index + 1,
+          step.operation.kind().symbol(),
+          step.operation.operands().1
+        )
+      };
+      plan.push_str(&line);
+      predicted = step.predicted.is_some();
+    }
+    plan
+  }
+
+  /// Solves `a * x + b * y = e` and `c * x + d * y = f` with Cramer's rule, with the determinants
+  /// computed by the calculator's `checked_multiply` and `checked_subtract`.
+  ///
+  /// Returns `None` if the determinant is zero, i.e. there is no unique solution, the solution
+  /// isn't integral, or computing a determinant fails, e.g. because it overflows.
+  pub fn solve_2x2(&self, a: i32, b: i32, c: i32, d: i32, e: i32, f: i32) -> Option<(i32, i32)> {
+    let calculator = &self.i32_calculator;
+    let determinant = |p, q, r, s| {
+      let products = (
+        calculator.checked_multiply(p, s).ok()?,
+        calculator.checked_multiply(q, r).ok()?,
+      );
+      calculator.checked_subtract(products.0, products.1).ok()
+    };
+
+    let det = determinant(a, b, c, d)?;
+    if det == 0 {
+      return None;
+    }
+    let solve = |numerator: i32| match numerator.checked_rem(det)? {
+      0 => numerator.checked_div(det),
+      _ => None,
+    };
+    Some((
+      solve(determinant(e, b, f, d)?)?,
+      solve(determinant(a, e, c, f)?)?,
+    ))
+  }
+
+  /// Returns `x / y` as an `f64`, from the calculator's
+  /// [`checked_divide`](trait.I32Calculator.html#method.checked_divide) for the integer part and
+  /// [`checked_modulo`](trait.I32Calculator.html#method.checked_modulo) for the remainder.
+  ///
+  /// Fails if `y` is zero or the division overflows.
+  pub fn ratio(&self, x: i32, y: i32) -> Result<f64, CalculatorError> {
+    let quotient = self.i32_calculator.checked_divide(x, y)?;
+    let remainder = self.i32_calculator.checked_modulo(x, y)?;
+    Ok(f64::from(quotient) + f64::from(remainder) / f64::from(y))
+  }
+
+  /// Linearly interpolates from `a` to `b` at `t_num / t_den`, i.e. `a + (b - a) * t_num / t_den`
+  /// with the calculator's checked methods.
+  ///
+  /// Fails if `t_den` is zero or any step overflows.
+  pub fn lerp(&self, a: i32, b: i32, t_num: i32, t_den: i32) -> Result<i32, CalculatorError> {
+    let distance = self.i32_calculator.checked_subtract(b, a)?;
+    let scaled = self.i32_calculator.checked_multiply(distance, t_num)?;
+    let offset = self.i32_calculator.checked_divide(scaled, t_den)?;
+    self.i32_calculator.checked_add(a, offset)
+  }
+
+  /// Returns the exponentially weighted moving average of `values` with a smoothing factor of
+  /// `alpha_num / alpha_den`, or `0` if there are no values.
+  ///
+  /// The average starts at the first value and each later value `v` updates it to
+  /// `(alpha_num * v + (alpha_den - alpha_num) * average) / alpha_den` with the calculator's
+  /// checked methods, so every step rounds toward zero like `/`.
+  ///
+  /// Fails if `alpha_den` is zero or any step overflows.
+  pub fn ewma(
+    &self,
+    values: &[i32],
+    alpha_num: i32,
+    alpha_den: i32,
+  ) -> Result<i32, CalculatorError> {
+    if alpha_den == 0 {
+      return Err(CalculatorError::DivisionByZero {
+        dividend: alpha_num,
+      });
+    }
+    let Some((&first, rest)) = values.split_first() else {
+      return Ok(0);
+    };
+    let calc = &self.i32_calculator;
+    let keep = calc.checked_subtract(alpha_den, alpha_num)?;
+    rest.iter().try_fold(first, |average, &value| {
+      let weighted = calc.checked_add(
+        calc.checked_multiply(alpha_num, value)?,
+        calc.checked_multiply(keep, average)?,
+      )?;
+      calc.checked_divide(weighted, alpha_den)
+    })
+  }
+
+  /// Returns `n!` computed with repeated checked multiplication, or an error if it doesn't fit in
+  /// `i32`.
+  pub fn factorial(&self, n: u32) -> Result<i32, CalculatorError> {
+    // 13! already overflows, so the folding stops long before `factor` could exceed `i32::MAX`.
+    (2..=n).try_fold(1, |product, factor| {
+      self.i32_calculator.checked_multiply(product, factor as i32)
+    })
+  }
+
+  /// Returns the greatest common divisor of `values`, using the calculator's
+  /// [`gcd`](trait.I32Calculator.html#method.gcd).
+  ///
+  /// Returns `0` for an empty slice, since `0` is the identity of `gcd`.
+  pub fn gcd_all(&self, values: &[i32]) -> i32 {
+    values
+      .iter()
+      .fold(0, |divisor, &value| self.i32_calculator.gcd(divisor, value))
+  }
+
+  /// Returns the result of running `script` using checked arithmetic.
+  ///
+  /// A script is a number followed by any number of operator and number pairs, separated by
+  /// whitespace, e.g. `"3 + 4 * 2"`. Operators are applied from left to right, so that script
+  /// returns `14`.
+  ///
+  /// This never panics as long as the calculator's checked methods don't, whatever `script` is.
+  pub fn run_script(&self, script: &str) -> Result<i32, ScriptError> {
+    fn number(token: &str) -> Result<i32, ScriptError> {
+      token.parse().map_err(|_| ScriptError::InvalidNumber {
+        token: token.into(),
+      })
+    }
+
+    let mut tokens = script.split_whitespace();
+    let mut output = number(tokens.next().ok_or(ScriptError::Empty)?)?;
+    while let Some(operator) = tokens.next() {
+      let op = match operator {
+        "+" => OpKind::Add,
+        "-" => OpKind::Subtract,
+        "*" => OpKind::Multiply,
+        "/" => OpKind::Divide,
+        _ => {
+          return Err(ScriptError::UnknownOperator {
+            token: operator.into(),
+          })
+        }
+      };
+      let operand = number(tokens.next().ok_or(ScriptError::MissingOperand)?)?;
+      output = op
+        .operation(output, operand)
+        .apply_checked(&*self.i32_calculator)?;
+    }
+    Ok(output)
+  }
+
+  /// Returns the value of the reverse Polish notation expression `tokens` using checked arithmetic,
+  /// e.g. `3 4 + 5 *` is `35`.
+  pub fn eval_rpn(&self, tokens: &[RpnToken]) -> Result<i32, RpnError> {
+    let mut stack = Vec::new();
+    for &token in tokens {
+      match token {
+        RpnToken::Num(value) => stack.push(value),
+        RpnToken::Op(op) => {
+          let op = match op {
+            '+' => OpKind::Add,
+            '-' => OpKind::Subtract,
+            '*' => OpKind::Multiply,
+            '/' => OpKind::Divide,
+            _ => return Err(RpnError::UnknownOperator { op }),
+          };
+          let y = stack.pop().ok_or(RpnError::StackUnderflow)?;
+          let x = stack.pop().ok_or(RpnError::StackUnderflow)?;
+          stack.push(op.operation(x, y).apply_checked(&*self.i32_calculator)?);
+        }
+      }
+    }
+    match stack[..] {
+      [result] => Ok(result),
+      [] => Err(RpnError::StackUnderflow),
+      _ => Err(RpnError::LeftoverOperands { count: stack.len() }),
+    }
+  }
+
+  /// Returns how `x` compares to `y`, from the sign of the calculator's `x - y`.
+  ///
+  /// When `x - y` would overflow, e.g. `compare(i32::MIN, 1)`, the calculator isn't called and `x`
+  /// and `y` are compared directly instead.
+  pub fn compare(&self, x: i32, y: i32) -> core::cmp::Ordering {
+    match x.checked_sub(y) {
+      Some(_) => self.i32_calculator.subtract(x, y).cmp(&0),
+      None => x.cmp(&y),
+    }
+  }
+
+  /// Runs [`cool_algorithm`](#method.cool_algorithm) on both applications for each of `inputs`
+  /// and returns `(input, self_result, other_result)` for each input where the results differ.
+  pub fn compare_workload<D: I32Calculator + ?Sized>(
+    &self,
+    other: &Application<D>,
+    inputs: &[i32],
+  ) -> Vec<(i32, i32, i32)> {
+    inputs
+      .iter()
+      .map(|&input| {
+        (
+          input,
+          self.cool_algorithm(input),
+          other.cool_algorithm(input),
+        )
+      })
+      .filter(|&(_, result, other_result)| result != other_result)
+      .collect()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Application {
+  /// Returns an application that uses the calculator registered under `id`, taking it out of
+  /// `registry`.
+  pub fn from_registry(registry: &mut CalculatorRegistry, id: &str) -> Option<Self> {
+    registry.remove(id).map(Application::new)
+  }
+
+  /// Returns the combined [`cost`](enum.Operation.html#method.cost) of `operations`.
+  pub fn total_cost(operations: &[Operation]) -> u32 {
+    operations.iter().map(Operation::cost).sum()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<dyn I32Calculator> for Application {
+  fn as_ref(&self) -> &dyn I32Calculator {
+    &*self.i32_calculator
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator> AsRef<dyn I32Calculator> for Application<C> {
+  fn as_ref(&self) -> &dyn I32Calculator {
+    &*self.i32_calculator
+  }
+}
+
+/// Clones the calculator, the overflow guard and scale settings and the observers, which are then
+/// notified by both applications.
+#[cfg(feature = "alloc")]
+impl<C: I32Calculator + Clone> Clone for Application<C> {
+  fn clone(&self) -> Self {
+    Application {
+      i32_calculator: self.i32_calculator.clone(),
+      overflow_guard: self.overflow_guard,
+      frac_bits: self.frac_bits,
+      #[cfg(feature = "std")]
+      observers: std::sync::Mutex::new(self.observers().clone()),
+    }
+  }
+}
+
+/// Clones the application with the calculator's
+/// [`boxed_clone`](trait.I32Calculator.html#method.boxed_clone), like
+/// [`try_clone`](struct.Application.html#method.try_clone).
+///
+/// # Panics
+///
+/// Panics if the calculator can't be copied, i.e. its `boxed_clone` returns `None`. Use
+/// `try_clone` when that isn't known.
+#[cfg(feature = "alloc")]
+impl Clone for Application {
+  fn clone(&self) -> Self {
+    self
+      .try_clone()
+      .expect("the calculator's boxed_clone returned None")
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<CalculatorConfig> for Application {
+  type Error = ConfigError;
+
+  fn try_from(config: CalculatorConfig) -> Result<Self, ConfigError> {
+    Ok(Application::new(config.build()?))
+  }
+}
+
+#[cfg(feature = "rayon")]
+impl<C: I32Calculator + Sync + ?Sized> Application<C> {
+  /// Runs [`cool_algorithm`](#method.cool_algorithm) on each of `inputs` in parallel on rayon's
+  /// thread pool, returning the results in the same order.
+  pub fn parallel_map(&self, inputs: &[i32]) -> Vec<i32> {
+    use rayon::prelude::*;
+
+    inputs.par_iter().map(|&x| self.cool_algorithm(x)).collect()
+  }
+}
+
+/// An [`Application`](struct.Application.html) whose
+/// [`cool_algorithm`](#method.cool_algorithm) remembers its result for each input, so repeating an
+/// input doesn't call the calculator again.
+#[cfg(feature = "std")]
+pub struct MemoizedApplication<C: I32Calculator + ?Sized = dyn I32Calculator> {
+  application: Application<C>,
+  results: core::cell::RefCell<std::collections::HashMap<i32, i32>>,
+}
+
+#[cfg(feature = "std")]
+impl<C: I32Calculator + ?Sized> MemoizedApplication<C> {
+  /// Returns the wrapped application.
+  pub fn application(&self) -> &Application<C> {
+    &self.application
+  }
+
+  /// [`Application::cool_algorithm`](struct.Application.html#method.cool_algorithm), or its
+  /// remembered result for `x`.
+  pub fn cool_algorithm(&self, x: i32) -> i32 {
+    if let Some(&result) = self.results.borrow().get(&x) {
+      return result;
+    }
+    let result = self.application.cool_algorithm(x);
+    self.results.borrow_mut().insert(x, result);
+    result
+  }
+}
+
+/// Helpers for testing calculators, especially decorators.
+#[cfg(feature = "alloc")]
+pub mod test_support {
+  #[cfg(feature = "std")]
+  use super::I32Calculator;
+  use super::IdentityCalculator;
+  use alloc::rc::Rc;
+
+  /// Asserts that the value returned by `build` releases the calculator it is given when dropped.
+  ///
+  /// `build` receives an `Rc` to an [`IdentityCalculator`](../struct.IdentityCalculator.html),
+  /// typically wrapping it in a decorator. The `Rc` implements
+  /// [`I32Calculator`](../trait.I32Calculator.html), so it can be passed straight to e.g.
+  /// [`CountingCalculator::new`](../struct.CountingCalculator.html#method.new).
+  pub fn assert_no_leak<F, D>(build: F)
+  where
+    F: FnOnce(Rc<IdentityCalculator>) -> D,
+  {
+    let calculator = Rc::new(IdentityCalculator);
+    drop(build(calculator.clone()));
+    assert_eq!(
+      Rc::strong_count(&calculator),
+      1,
+      "the calculator outlived the value built from it"
+    );
+  }
+
+  /// Wraps a calculator, typically a mock, and asserts when dropped that it was called exactly the
+  /// expected number of times.
+  ///
+  /// Unlike a mock's own checks, this doesn't depend on which expectations were set up, so a
+  /// forgotten expectation can't hide a missing call. Nothing is asserted if the thread is already
+  /// panicking, so a failing test reports its original panic.
+  #[cfg(feature = "std")]
+  pub struct VerifyOnDrop<M: I32Calculator> {
+    i32_calculator: M,
+    expected_calls: usize,
+    calls: core::cell::Cell<usize>,
+  }
+
+  #[cfg(feature = "std")]
+  impl<M: I32Calculator> VerifyOnDrop<M> {
+    /// Returns a guard that expects `i32_calculator` to be called `expected_calls` times in total.
+    pub fn new(i32_calculator: M, expected_calls: usize) -> Self {
+      VerifyOnDrop {
+        i32_calculator,
+        expected_calls,
+        calls: core::cell::Cell::new(0),
+      }
+    }
+
+    fn call(&self) -> &M {
+      self.calls.set(self.calls.get() + 1);
+      &self.i32_calculator
+    }
+  }
+
+  #[cfg(feature = "std")]
+  impl<M: I32Calculator> I32Calculator for VerifyOnDrop<M> {
+    fn add(&self, x: i32, y: i32) -> i32 {
+      self.call().add(x, y)
+    }
+
+    fn subtract(&self, x: i32, y: i32) -> i32 {
+      self.call().subtract(x, y)
+    }
+
+    fn multiply(&self, x: i32, y: i32) -> i32 {
+      self.call().multiply(x, y)
+    }
+
+    fn divide(&self, x: i32, y: i32) -> i32 {
+      self.call().divide(x, y)
+    }
+  }
+
+  #[cfg(feature = "std")]
+  impl<M: I32Calculator> Drop for VerifyOnDrop<M> {
+    fn drop(&mut self) {
+      if !std::thread::panicking() {
+        assert_eq!(
+          self.calls.get(),
+          self.expected_calls,
+          "the calculator was called {} times, expected {}",
+          self.calls.get(),
+          self.expected_calls
+        );
+      }
+    }
+  }
+
+  /// Builder for a [`MockI32Calculator`](../struct.MockI32Calculator.html) that expects each call
+  /// added to it exactly once, with the given operands, and returns the given result.
+  ///
+  /// This is only available to the crate's own tests, because the mock is.
+  #[cfg(all(test, feature = "std"))]
+  #[derive(Default)]
+  pub struct ExpectCalculator {
+    mock_i32_calculator: super::MockI32Calculator,
+  }
+
+  #[cfg(all(test, feature = "std"))]
+  impl ExpectCalculator {
+    /// Returns a builder without any expectations.
+    pub fn new() -> Self {
+      Self::default()
+    }
+
+    /// Expects `add(x, y)` once, returning `result`.
+    pub fn add(mut self, x: i32, y: i32, result: i32) -> Self {
+      self
+        .mock_i32_calculator
+        .expect_add()
+        .times(1)
+        .with(mockall::predicate::eq(x), mockall::predicate::eq(y))
+        .return_const(result);
+      self
+    }
+
+    /// Expects `subtract(x, y)` once, returning `result`.
+    pub fn subtract(mut self, x: i32, y: i32, result: i32) -> Self {
+      self
+        .mock_i32_calculator
+        .expect_subtract()
+        .times(1)
+        .with(mockall::predicate::eq(x), mockall::predicate::eq(y))
+        .return_const(result);
+      self
+    }
+
+    /// Expects `multiply(x, y)` once, returning `result`.
+    pub fn multiply(mut self, x: i32, y: i32, result: i32) -> Self {
+      self
+        .mock_i32_calculator
+        .expect_multiply()
+        .times(1)
+        .with(mockall::predicate::eq(x), mockall::predicate::eq(y))
+        .return_const(result);
+      self
+    }
+
+    /// Expects `divide(x, y)` once, returning `result`.
+    pub fn divide(mut self, x: i32, y: i32, result: i32) -> Self {
+      self
+        .mock_i32_calculator
+        .expect_divide()
+        .times(1)
+        .with(mockall::predicate::eq(x), mockall::predicate::eq(y))
+        .return_const(result);
+      self
+    }
+
+    /// Returns the configured mock.
+    pub fn build(self) -> super::MockI32Calculator {
+      self.mock_i32_calculator
+    }
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use mockall::predicate;
+
+  #[test]
+  fn cool_algorithm_does_nothing() {
+    let number = 100;
+
+    // mock object that is I32Calculator
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    // set up our expectations
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(number), predicate::eq(0))
+      .return_const(number);
+
+    mock_i32_calculator
+      .expect_subtract()
+      .times(1)
+      .with(predicate::eq(number), predicate::eq(0))
+      .return_const(number);
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(number), predicate::eq(1))
+      .return_const(number);
+
+    mock_i32_calculator
+      .expect_divide()
+      .times(1)
+      .with(predicate::eq(number), predicate::eq(1))
+      .return_const(number);
+
+    // create our application with our mock calculator
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    // run our unit test of the cool_algorithm
+    assert_eq!(app.cool_algorithm(number), number);
+  }
+
+  #[test]
+  fn f64_as_i32_rounds_results() {
+    let mut mock_f64_calculator = MockF64Calculator::new();
+
+    mock_f64_calculator
+      .expect_divide()
+      .times(1)
+      .with(predicate::eq(7.0), predicate::eq(2.0))
+      .return_const(3.5);
+
+    mock_f64_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(i32::MAX as f64), predicate::eq(2.0))
+      .return_const(i32::MAX as f64 * 2.0);
+
+    let calculator = F64AsI32(mock_f64_calculator);
+
+    // ties round away from zero and out-of-range results saturate
+    assert_eq!(calculator.divide(7, 2), 4);
+    assert_eq!(calculator.multiply(i32::MAX, 2), i32::MAX);
+  }
+
+  #[test]
+  fn batch_tagged_preserves_tags() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(1), predicate::eq(2))
+      .return_const(3);
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(4))
+      .return_const(12);
+
+    let operations = [
+      Operation::Add(1, 2).tagged("request-1"),
+      Operation::Multiply(3, 4).into(),
+    ];
+
+    assert_eq!(
+      mock_i32_calculator.batch_tagged(&operations),
+      vec![
+        TaggedResult {
+          result: 3,
+          tag: Some("request-1".into()),
+        },
+        TaggedResult {
+          result: 12,
+          tag: None,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn builder_accepts_valid_configuration() {
+    let calculator = ExternalI32Calculator::builder()
+      .overflow_policy(OverflowPolicy::Saturate)
+      .bounds(-100, 100)
+      .build();
+
+    assert!(calculator.is_ok());
+  }
+
+  #[test]
+  fn builder_rejects_conflicting_configuration() {
+    let wider_than_i32 = ExternalI32Calculator::builder()
+      .overflow_policy(OverflowPolicy::Saturate)
+      .bounds(0, i64::from(i32::MAX) + 1)
+      .build();
+    let inverted = ExternalI32Calculator::builder().bounds(10, -10).build();
+
+    assert_eq!(
+      wider_than_i32.err(),
+      Some(ConfigError::BoundsOutOfRange {
+        min: 0,
+        max: i64::from(i32::MAX) + 1,
+      })
+    );
+    assert_eq!(
+      inverted.err(),
+      Some(ConfigError::InvertedBounds { min: 10, max: -10 })
+    );
+  }
+
+  #[test]
+  fn bounded_calculator_applies_overflow_policy() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+    mock_i32_calculator.expect_add().return_const(12);
+
+    let saturating =
+      BoundedCalculator::new(mock_i32_calculator, 0, 9, OverflowPolicy::Saturate).unwrap();
+    assert_eq!(saturating.add(6, 6), 9);
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+    mock_i32_calculator.expect_add().return_const(12);
+
+    let wrapping = BoundedCalculator::new(mock_i32_calculator, 0, 9, OverflowPolicy::Wrap).unwrap();
+    assert_eq!(wrapping.add(6, 6), 2);
+  }
+
+  #[test]
+  fn only_add_and_multiply_are_commutative() {
+    assert!(Operation::Add(1, 2).is_commutative());
+    assert!(!Operation::Subtract(1, 2).is_commutative());
+    assert!(Operation::Multiply(1, 2).is_commutative());
+    assert!(!Operation::Divide(1, 2).is_commutative());
+  }
+
+  #[test]
+  fn cool_algorithm_simplifies_to_nothing() {
+    let number = 100;
+    let cool_algorithm = [
+      Operation::Add(number, 0),
+      Operation::Subtract(number, 0),
+      Operation::Multiply(number, 1),
+      Operation::Divide(number, 1),
+    ];
+
+    assert_eq!(simplify(&cool_algorithm), vec![]);
+    assert_eq!(
+      simplify(&[Operation::Add(number, 1), Operation::Divide(number, 1)]),
+      vec![Operation::Add(number, 1)]
+    );
+  }
+
+  #[test]
+  fn overflow_error_displays_operands() {
+    let error = CalculatorError::Overflow {
+      op: "multiply",
+      x: i32::MAX,
+      y: 2,
+    };
+
+    assert_eq!(
+      error.to_string(),
+      format!("multiply of {} and 2 overflows i32", i32::MAX)
+    );
+  }
+
+  #[test]
+  fn calculator_errors_convert_into_io_errors() {
+    let division_by_zero = std::io::Error::from(CalculatorError::DivisionByZero { dividend: 7 });
+    let overflow = std::io::Error::from(CalculatorError::Overflow {
+      op: "add",
+      x: i32::MAX,
+      y: 1,
+    });
+
+    assert_eq!(division_by_zero.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+      division_by_zero.to_string(),
+      "attempted to divide 7 by zero"
+    );
+    assert_eq!(overflow.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+      overflow.to_string(),
+      format!("add of {} and 1 overflows i32", i32::MAX)
+    );
+  }
+
+  #[test]
+  fn shadow_calculator_counts_mismatches() {
+    let mut primary = MockI32Calculator::new();
+    primary
+      .expect_add()
+      .times(2)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(5);
+
+    let mut shadow = MockI32Calculator::new();
+    shadow
+      .expect_add()
+      .times(2)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(6);
+
+    let calculator = ShadowCalculator::new(primary, shadow);
+
+    assert_eq!(calculator.add(2, 3), 5);
+    assert_eq!(calculator.mismatches(), 1);
+    assert_eq!(calculator.add(2, 3), 5);
+    assert_eq!(calculator.mismatches(), 2);
+  }
+
+  #[test]
+  fn factorial_multiplies_until_overflow() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.factorial(0), Ok(1));
+    assert_eq!(app.factorial(5), Ok(120));
+    assert_eq!(app.factorial(12), Ok(479_001_600));
+    assert_eq!(
+      app.factorial(13),
+      Err(CalculatorError::Overflow {
+        op: "multiply",
+        x: 479_001_600,
+        y: 13,
+      })
+    );
+  }
+
+  #[test]
+  fn fold_ops_feeds_each_result_into_the_next_operation() {
+    let mut sequence = mockall::Sequence::new();
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .in_sequence(&mut sequence)
+      .with(predicate::eq(5), predicate::eq(2))
+      .return_const(7);
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .in_sequence(&mut sequence)
+      .with(predicate::eq(7), predicate::eq(3))
+      .return_const(21);
+
+    mock_i32_calculator
+      .expect_subtract()
+      .times(1)
+      .in_sequence(&mut sequence)
+      .with(predicate::eq(21), predicate::eq(1))
+      .return_const(20);
+
+    let operations = [
+      Operation::Add(0, 2),
+      Operation::Multiply(0, 3),
+      Operation::Subtract(0, 1),
+    ];
+
+    assert_eq!(fold_ops(&mock_i32_calculator, 5, &operations), 20);
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn shared_mut_calculator_serializes_access() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let in_use = Arc::new(AtomicBool::new(false));
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    // fails if a second call starts before the first one is done
+    let busy = in_use.clone();
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(move |x, _| {
+        assert!(!busy.swap(true, Ordering::SeqCst));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        busy.store(false, Ordering::SeqCst);
+        x
+      });
+    mock_i32_calculator
+      .expect_subtract()
+      .times(2)
+      .returning(|x, _| x);
+    mock_i32_calculator
+      .expect_multiply()
+      .times(2)
+      .returning(|x, _| x);
+    mock_i32_calculator
+      .expect_divide()
+      .times(2)
+      .returning(|x, _| x);
+
+    let shared = SharedMutCalculator::new(mock_i32_calculator);
+
+    let handles: Vec<_> = [1, 2]
+      .into_iter()
+      .map(|number| {
+        let i32_calculator = shared.clone();
+        std::thread::spawn(move || {
+          let app = Application::new(Box::new(i32_calculator));
+          app.cool_algorithm(number)
+        })
+      })
+      .collect();
+
+    let results: Vec<_> = handles
+      .into_iter()
+      .map(|handle| handle.join().unwrap())
+      .collect();
+    assert_eq!(results, vec![1, 2]);
+  }
+
+  #[test]
+  fn divide_all_partitions_quotients_and_errors() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    let (quotients, errors) = app.divide_all(&[(10, 2), (3, 0), (9, 3), (-4, 0)]);
+
+    assert_eq!(quotients, vec![5, 3]);
+    assert_eq!(
+      errors,
+      vec![
+        (1, CalculatorError::DivisionByZero { dividend: 3 }),
+        (3, CalculatorError::DivisionByZero { dividend: -4 }),
+      ]
+    );
+  }
+
+  #[test]
+  fn batch_array_returns_fixed_size_results() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_subtract()
+      .times(1)
+      .with(predicate::eq(9), predicate::eq(4))
+      .return_const(5);
+
+    mock_i32_calculator
+      .expect_divide()
+      .times(1)
+      .with(predicate::eq(9), predicate::eq(3))
+      .return_const(3);
+
+    let results: [i32; 2] =
+      mock_i32_calculator.batch_array::<2>([Operation::Subtract(9, 4), Operation::Divide(9, 3)]);
+
+    assert_eq!(results, [5, 3]);
+  }
+
+  #[test]
+  fn operations_have_relative_costs() {
+    assert_eq!(Operation::Add(1, 2).cost(), 1);
+    assert_eq!(Operation::Subtract(1, 2).cost(), 1);
+    assert_eq!(Operation::Multiply(1, 2).cost(), 3);
+    assert_eq!(Operation::Divide(1, 2).cost(), 5);
+
+    assert_eq!(
+      Application::total_cost(&[
+        Operation::Add(1, 2),
+        Operation::Divide(1, 2),
+        Operation::Multiply(1, 2),
+        Operation::Add(1, 2),
+      ]),
+      10
+    );
+  }
+
+  #[test]
+  fn program_runs_against_each_input() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(2)
+      .returning(|x, y| x * y);
+
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(|x, y| x + y);
+
+    let program = Program::new(vec![Operation::Multiply(0, 2), Operation::Add(0, 1)]);
+
+    assert_eq!(program.run(&mock_i32_calculator, 3), 7);
+    assert_eq!(program.run(&mock_i32_calculator, 10), 21);
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test(start_paused = true)]
+  async fn async_retrying_calculator_backs_off_exponentially() {
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    // fails twice before succeeding
+    let mut calls = 0;
+    mock_async_i32_calculator
+      .expect_add()
+      .times(3)
+      .with(predicate::eq(2), predicate::eq(3))
+      .returning(move |x, y| {
+        calls += 1;
+        let result = if calls <= 2 {
+          Err(CalculatorError::Transient)
+        } else {
+          Ok(x + y)
+        };
+        Box::pin(async move { result })
+      });
+
+    let calculator = AsyncRetryingCalculator::new(
+      mock_async_i32_calculator,
+      std::time::Duration::from_millis(100),
+      3,
+    );
+
+    let start = tokio::time::Instant::now();
+    assert_eq!(calculator.add(2, 3).await, Ok(5));
+    // slept 100ms after the first failure and 200ms after the second
+    assert_eq!(start.elapsed(), std::time::Duration::from_millis(300));
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test(start_paused = true)]
+  async fn async_retrying_calculator_only_retries_transient_errors() {
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_divide()
+      .times(1)
+      .returning(|x, _| {
+        Box::pin(async move { Err(CalculatorError::DivisionByZero { dividend: x }) })
+      });
+    let mut calls = 0;
+    mock_async_i32_calculator
+      .expect_add()
+      .times(3)
+      .returning(move |x, y| {
+        calls += 1;
+        let result = if calls <= 2 {
+          Err(CalculatorError::Transient)
+        } else {
+          Ok(x + y)
+        };
+        Box::pin(async move { result })
+      });
+
+    // the backoff saturates instead of overflowing
+    let calculator =
+      AsyncRetryingCalculator::new(mock_async_i32_calculator, std::time::Duration::MAX, 10);
+
+    assert_eq!(
+      calculator.divide(7, 0).await,
+      Err(CalculatorError::DivisionByZero { dividend: 7 })
+    );
+    assert_eq!(calculator.add(2, 3).await, Ok(5));
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test]
+  async fn process_stream_maps_cool_algorithm_in_order() {
+    use futures::StreamExt;
+
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(|x, y| Box::pin(async move { Ok(x + y) }));
+    mock_async_i32_calculator
+      .expect_subtract()
+      .times(2)
+      .returning(|x, y| Box::pin(async move { Ok(x - y) }));
+    mock_async_i32_calculator
+      .expect_multiply()
+      .times(2)
+      .returning(|x, y| Box::pin(async move { Ok(x * y) }));
+    mock_async_i32_calculator
+      .expect_divide()
+      .times(2)
+      .returning(|x, y| Box::pin(async move { Ok(x / y) }));
+
+    let app = AsyncApplication {
+      async_i32_calculator: Box::new(mock_async_i32_calculator),
+    };
+
+    let results: Vec<_> = app
+      .process_stream(futures::stream::iter([3, 7]))
+      .collect()
+      .await;
+
+    assert_eq!(results, vec![Ok(3), Ok(7)]);
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn async_calculator_runs_without_tokio() {
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(6), predicate::eq(7))
+      .returning(|x, y| Box::pin(async move { Ok(x * y) }));
+
+    assert_eq!(
+      futures::executor::block_on(
+        Operation::Multiply(6, 7).apply_async(&mock_async_i32_calculator)
+      ),
+      Ok(42)
+    );
+  }
+
+  #[test]
+  fn evaluate_checked_short_circuits_on_nested_errors() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(2))
+      .return_const(Ok(0));
+
+    mock_i32_calculator
+      .expect_checked_divide()
+      .times(1)
+      .with(predicate::eq(1), predicate::eq(0))
+      .return_const(Err(CalculatorError::DivisionByZero { dividend: 1 }));
+
+    // no checked_add expectation since the addition is never reached
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    // (1 / (2 - 2)) + 3
+    let expr = Expr::Add(
+      Box::new(Expr::Divide(
+        Box::new(Expr::Literal(1)),
+        Box::new(Expr::Subtract(
+          Box::new(Expr::Literal(2)),
+          Box::new(Expr::Literal(2)),
+        )),
+      )),
+      Box::new(Expr::Literal(3)),
+    );
+
+    assert_eq!(
+      app.evaluate_checked(&expr),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+  }
+
+  #[test]
+  fn calculator_as_downcasts_to_concrete_type() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert!(app.calculator_as::<IdentityCalculator>().is_some());
+    assert!(app.calculator_as::<ExternalI32Calculator>().is_none());
+  }
+
+  #[test]
+  fn checked_modulo_rejects_zero_and_overflow() {
+    // both are rejected before the external service would be called
+    assert_eq!(
+      ExternalI32Calculator.checked_modulo(7, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 7 })
+    );
+    assert_eq!(
+      ExternalI32Calculator.checked_modulo(i32::MIN, -1),
+      Err(CalculatorError::Overflow {
+        op: "modulo",
+        x: i32::MIN,
+        y: -1,
+      })
+    );
+
+    assert_eq!(IdentityCalculator.checked_modulo(7, 3), Ok(1));
+    assert_eq!(IdentityCalculator.checked_modulo(-7, 3), Ok(-1));
+  }
+
+  #[test]
+  fn observed_calculator_reports_each_operation() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let number = 100;
+    let observed = Rc::new(RefCell::new(Vec::new()));
+
+    let recorder = observed.clone();
+    let app = Application::new(Box::new(ObservedCalculator::new(
+      IdentityCalculator,
+      Box::new(move |operation, result| recorder.borrow_mut().push((operation, result))),
+    )));
+
+    assert_eq!(app.cool_algorithm(number), number);
+    assert_eq!(
+      *observed.borrow(),
+      vec![
+        (Operation::Add(number, 0), number),
+        (Operation::Subtract(number, 0), number),
+        (Operation::Multiply(number, 1), number),
+        (Operation::Divide(number, 1), number),
+      ]
+    );
+  }
+
+  #[test]
+  fn reduce_with_init_starts_from_init() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(3)
+      .returning(|x, y| x + y);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.reduce_with_init(10, &[1, 2, 3], OpKind::Add), 16);
+  }
+
+  #[test]
+  fn reduce_starts_from_first_value() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.reduce(&[10, 2, 3], OpKind::Add), 15);
+    assert_eq!(app.reduce(&[10, 2, 3], OpKind::Multiply), 60);
+    assert_eq!(app.reduce(&[10, 2, 3], OpKind::Subtract), 5);
+    assert_eq!(app.reduce(&[10, 2], OpKind::Divide), 5);
+    assert_eq!(app.reduce(&[7], OpKind::Subtract), 7);
+    assert_eq!(app.reduce(&[], OpKind::Subtract), 0);
+    assert_eq!(app.reduce(&[], OpKind::Divide), 1);
+  }
+
+  #[cfg(not(feature = "real-impl"))]
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn fallback_calculator_recovers_from_panics() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(Ok(5));
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(6);
+
+    // ExternalI32Calculator panics when called
+    let calculator = FallbackCalculator::new(ExternalI32Calculator, mock_i32_calculator);
+
+    assert_eq!(calculator.checked_add(2, 3), Ok(5));
+    assert_eq!(calculator.multiply(2, 3), 6);
+
+    let calculator = FallbackCalculator::new(ExternalI32Calculator, IdentityCalculator);
+
+    assert_eq!(calculator.checked_modulo(7, 3), Ok(1));
+    assert_eq!(calculator.divmod(7, 3), Ok((2, 1)));
+    assert_eq!(calculator.backend_name(), "external");
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn calculator_stats_serialize_per_operation_counts() {
+    let calculator = CountingCalculator::new(IdentityCalculator);
+    calculator.add(1, 2);
+    calculator.add(3, 4);
+    calculator.divide(8, 2);
+
+    let json = serde_json::to_value(calculator.stats()).unwrap();
+
+    assert_eq!(
+      json,
+      serde_json::json!({ "add": 2, "subtract": 0, "multiply": 0, "divide": 1 })
+    );
+  }
+
+  #[test]
+  fn approximate_sqrt_converges() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.approximate_sqrt(16, 10), Ok(4));
+    assert_eq!(app.approximate_sqrt(20, 10), Ok(4));
+    assert_eq!(app.approximate_sqrt(i32::MAX, 10), Ok(46_340));
+    assert_eq!(
+      app.approximate_sqrt(-1, 10),
+      Err(CalculatorError::NegativeSquareRoot { radicand: -1 })
+    );
+
+    // zero is answered without calling the panicking external calculator
+    let app = Application::new(Box::new(ExternalI32Calculator));
+    assert_eq!(app.approximate_sqrt(0, 10), Ok(0));
+  }
+
+  #[test]
+  fn typed_calculator_tracks_units() {
+    struct Meters;
+    struct Seconds;
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(4))
+      .return_const(7);
+
+    mock_i32_calculator
+      .expect_divide()
+      .times(1)
+      .with(predicate::eq(7), predicate::eq(7))
+      .return_const(1);
+
+    let calculator = TypedCalculator(mock_i32_calculator);
+
+    let distance: Quantity<Meters> = calculator.add(Quantity::new(3), Quantity::new(4));
+    let speed: Quantity<Quotient<Meters, Seconds>> =
+      calculator.divide(distance, Quantity::<Seconds>::new(7));
+
+    assert_eq!(distance, Quantity::new(7));
+    assert_eq!(speed.value(), 1);
+  }
+
+  #[test]
+  fn try_process_stream_continues_past_errors() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(3)
+      .returning(|x, y| match x {
+        2 => Err(CalculatorError::Overflow { op: "add", x, y }),
+        _ => Ok(x),
+      });
+
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .times(2)
+      .returning(|x, _| Ok(x));
+
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .times(2)
+      .returning(|x, _| Ok(x));
+
+    mock_i32_calculator
+      .expect_checked_divide()
+      .times(2)
+      .returning(|x, _| Ok(x));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    let results: Vec<_> = app.try_process_stream([1, 2, 3].into_iter()).collect();
+
+    assert_eq!(
+      results,
+      vec![
+        Ok(1),
+        Err(CalculatorError::Overflow {
+          op: "add",
+          x: 2,
+          y: 0,
+        }),
+        Ok(3),
+      ]
+    );
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn thread_local_counting_calculator_counts_per_thread() {
+    type Counting = ThreadLocalCountingCalculator<IdentityCalculator>;
+
+    let app = Application::new(Box::new(ThreadLocalCountingCalculator(IdentityCalculator)));
+    app.cool_algorithm(100);
+    // calculators wrapping other types count into the same stats
+    Application::new(Box::new(ThreadLocalCountingCalculator(
+      ConstantCalculator::new(0, 0, 0, 1),
+    )))
+    .cool_algorithm(100);
+
+    assert_eq!(
+      Counting::snapshot(),
+      CalculatorStats {
+        add: 2,
+        subtract: 2,
+        multiply: 2,
+        divide: 2,
+      }
+    );
+
+    // other threads have their own counts
+    std::thread::spawn(|| assert_eq!(Counting::snapshot().total(), 0))
+      .join()
+      .unwrap();
+
+    Counting::reset();
+    assert_eq!(Counting::snapshot(), CalculatorStats::default());
+  }
+
+  #[test]
+  fn calculator_macro_defines_a_calculator() {
+    calculator! {
+      struct Native;
+      add => |x, y| x + y,
+      subtract => |x, y| x - y,
+      multiply => |x, y| x * y,
+      divide => |x, y| x / y,
+    }
+
+    let app = Application::new(Box::new(Native));
+
+    assert_eq!(app.cool_algorithm(100), 100);
+  }
+
+  #[test]
+  fn solve_quadratic_finds_integral_roots() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    // x² - 5x + 6 = (x - 2)(x - 3)
+    assert_eq!(
+      app.solve_quadratic(1, -5, 6),
+      Ok(QuadraticRoots::TwoRoots(2, 3))
+    );
+    // x² - 2x + 1 = (x - 1)²
+    assert_eq!(
+      app.solve_quadratic(1, -2, 1),
+      Ok(QuadraticRoots::OneRoot(1))
+    );
+    // 2x² - 3x + 1 = (2x - 1)(x - 1)
+    assert_eq!(
+      app.solve_quadratic(2, -3, 1),
+      Ok(QuadraticRoots::NonIntegralRoots)
+    );
+    // x² + 1
+    assert_eq!(
+      app.solve_quadratic(1, 0, 1),
+      Ok(QuadraticRoots::NoRealRoots)
+    );
+    // 3x + 1
+    assert_eq!(
+      app.solve_quadratic(0, 3, 1),
+      Ok(QuadraticRoots::NotQuadratic)
+    );
+    // b² doesn't fit in i32
+    assert_eq!(
+      app.solve_quadratic(1, 46_341, 0),
+      Err(CalculatorError::Overflow {
+        op: "multiply",
+        x: 46_341,
+        y: 46_341,
+      })
+    );
+  }
+
+  #[test]
+  fn arg_histogram_calculator_counts_operand_pairs() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(4)
+      .returning(|x, y| x + y);
+
+    let calculator = ArgHistogramCalculator::new(mock_i32_calculator);
+    for _ in 0..3 {
+      calculator.add(2, 3);
+    }
+    calculator.add(3, 2);
+
+    assert_eq!(calculator.histogram(OpKind::Add).get(&(2, 3)), Some(&3));
+    assert_eq!(calculator.histogram(OpKind::Add).get(&(3, 2)), Some(&1));
+    assert!(calculator.histogram(OpKind::Multiply).is_empty());
+  }
+
+  #[test]
+  fn unfold_repeats_the_operation() {
+    let operations: Vec<Operation> = Operation::Multiply(5, 2).unfold(3).collect();
+    assert_eq!(operations, vec![Operation::Multiply(5, 2); 3]);
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_multiply()
+      .times(3)
+      .returning(|x, y| x * y);
+
+    assert_eq!(fold_ops(&mock_i32_calculator, 5, &operations), 40);
+  }
+
+  #[test]
+  fn accumulator_try_chain_stops_at_the_first_error() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .with(predicate::eq(10), predicate::eq(1))
+      .times(1)
+      .returning(|x, y| Ok(x + y));
+    mock_i32_calculator
+      .expect_checked_divide()
+      .with(predicate::eq(11), predicate::eq(0))
+      .times(1)
+      .returning(|x, _| Err(CalculatorError::DivisionByZero { dividend: x }));
+
+    let mut accumulator = Accumulator::new(&mock_i32_calculator, 10);
+    let result = (|| -> Result<i32, CalculatorError> {
+      Ok(accumulator.try_add(1)?.try_divide(0)?.try_add(1)?.value())
+    })();
+
+    assert_eq!(
+      result,
+      Err(CalculatorError::DivisionByZero { dividend: 11 })
+    );
+    assert_eq!(accumulator.value(), 11);
+  }
+
+  #[test]
+  fn tagged_borrows_static_tags_and_owns_dynamic_ones() {
+    let id = 7;
+    let static_tag = Operation::Add(1, 2).tagged("request");
+    let dynamic_tag = Operation::Add(1, 2).tagged(format!("request-{id}"));
+
+    assert!(matches!(static_tag.tag, Some(Cow::Borrowed("request"))));
+    assert!(matches!(dynamic_tag.tag, Some(Cow::Owned(ref tag)) if tag == "request-7"));
+  }
+
+  #[test]
+  fn compare_maps_the_sign_of_the_difference() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_subtract()
+      .times(3)
+      .returning(|x, y| x - y);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.compare(1, 2), core::cmp::Ordering::Less);
+    assert_eq!(app.compare(2, 2), core::cmp::Ordering::Equal);
+    assert_eq!(app.compare(3, 2), core::cmp::Ordering::Greater);
+    // overflows, so this is compared without calling `subtract`
+    assert_eq!(app.compare(i32::MIN, 1), core::cmp::Ordering::Less);
+  }
+
+  #[test]
+  fn gcd_all_folds_gcd_over_the_values() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    for (x, y, gcd) in [(0, 12, 12), (12, 18, 6), (6, 24, 6)] {
+      mock_i32_calculator
+        .expect_gcd()
+        .times(1)
+        .with(predicate::eq(x), predicate::eq(y))
+        .in_sequence(&mut sequence)
+        .return_const(gcd);
+    }
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.gcd_all(&[12, 18, 24]), 6);
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.gcd_all(&[]), 0);
+    assert_eq!(app.gcd_all(&[-9]), 9);
+  }
+
+  #[test]
+  fn run_script_runs_operators_from_left_to_right() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.run_script("3 + 4 * 2"), Ok(14));
+    assert_eq!(app.run_script("  -7\n/ 2 "), Ok(-3));
+    assert_eq!(app.run_script(""), Err(ScriptError::Empty));
+    assert_eq!(
+      app.run_script("3 + four"),
+      Err(ScriptError::InvalidNumber {
+        token: "four".into()
+      })
+    );
+    assert_eq!(
+      app.run_script("3 % 4"),
+      Err(ScriptError::UnknownOperator { token: "%".into() })
+    );
+    assert_eq!(app.run_script("3 +"), Err(ScriptError::MissingOperand));
+    assert_eq!(
+      app.run_script("3 / 0"),
+      Err(ScriptError::Calculator(CalculatorError::DivisionByZero {
+        dividend: 3
+      }))
+    );
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn shared_calculator_can_be_used_from_other_threads() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(|x, y| x + y);
+
+    let calculator: Box<dyn SharedCalculator> = Box::new(mock_i32_calculator);
+    let calculator = std::sync::Arc::new(calculator);
+
+    let handles: Vec<_> = (0..2)
+      .map(|i| {
+        let calculator = calculator.clone();
+        std::thread::spawn(move || calculator.add(i, 10))
+      })
+      .collect();
+    let mut results: Vec<i32> = handles
+      .into_iter()
+      .map(|handle| handle.join().unwrap())
+      .collect();
+    results.sort();
+
+    assert_eq!(results, vec![10, 11]);
+  }
+
+  #[test]
+  fn overflow_guard_switches_to_checked_arithmetic() {
+    // adding 0 can't overflow, but this calculator thinks it does
+    let mut guarded_mock_i32_calculator = MockI32Calculator::new();
+
+    guarded_mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .returning(|x, y| Err(CalculatorError::Overflow { op: "add", x, y }));
+
+    let guarded_app =
+      Application::new(Box::new(guarded_mock_i32_calculator)).with_overflow_guard(true);
+
+    assert_eq!(
+      guarded_app.guarded_cool_algorithm(i32::MAX),
+      Err(CalculatorError::Overflow {
+        op: "add",
+        x: i32::MAX,
+        y: 0
+      })
+    );
+
+    let mut unguarded_mock_i32_calculator = MockI32Calculator::new();
+
+    unguarded_mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .return_const(i32::MIN);
+    unguarded_mock_i32_calculator
+      .expect_subtract()
+      .times(1)
+      .returning(|x, _| x);
+    unguarded_mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .returning(|x, _| x);
+    unguarded_mock_i32_calculator
+      .expect_divide()
+      .times(1)
+      .returning(|x, _| x);
+
+    let unguarded_app =
+      Application::new(Box::new(unguarded_mock_i32_calculator)).with_overflow_guard(false);
+
+    assert_eq!(unguarded_app.guarded_cool_algorithm(i32::MAX), Ok(i32::MIN));
+  }
+
+  #[test]
+  fn coalesce_merges_consecutive_commutative_operations() {
+    let number = 100;
+
+    assert_eq!(
+      coalesce(&[
+        Operation::Add(number, 2),
+        Operation::Add(number, 3),
+        Operation::Subtract(number, 4),
+        Operation::Subtract(number, 5),
+      ]),
+      vec![
+        Operation::Add(number, 5),
+        Operation::Subtract(number, 4),
+        Operation::Subtract(number, 5),
+      ]
+    );
+    assert_eq!(
+      coalesce(&[
+        Operation::Multiply(number, i32::MAX),
+        Operation::Multiply(number, 2),
+      ]),
+      vec![
+        Operation::Multiply(number, i32::MAX),
+        Operation::Multiply(number, 2),
+      ]
+    );
+  }
+
+  #[test]
+  fn coalesce_keeps_operations_whose_intermediate_result_could_overflow() {
+    let number = 100;
+    let unmerged = [
+      [
+        Operation::Add(number, i32::MAX),
+        Operation::Add(number, -i32::MAX),
+      ],
+      [Operation::Add(number, -5), Operation::Add(number, 3)],
+      [
+        Operation::Multiply(number, i32::MAX),
+        Operation::Multiply(number, 0),
+      ],
+      [
+        Operation::Multiply(number, 2),
+        Operation::Multiply(number, -1),
+      ],
+    ];
+
+    for operations in unmerged {
+      assert_eq!(coalesce(&operations), operations);
+    }
+    assert_eq!(
+      coalesce(&[Operation::Add(number, -5), Operation::Add(number, -3)]),
+      [Operation::Add(number, -8)]
+    );
+    assert_eq!(
+      coalesce(&[
+        Operation::Multiply(number, -2),
+        Operation::Multiply(number, 3)
+      ]),
+      [Operation::Multiply(number, -6)]
+    );
+
+    // unmerged, the checked calculator still reports the intermediate overflow
+    let operations = coalesce(&[Operation::Add(0, i32::MAX), Operation::Add(0, -i32::MAX)]);
+    let result = operations.iter().try_fold(1, |output, operation| {
+      operation
+        .with_left(output)
+        .apply_checked(&IdentityCalculator)
+    });
+    assert_eq!(
+      result,
+      Err(CalculatorError::Overflow {
+        op: "add",
+        x: 1,
+        y: i32::MAX,
+      })
+    );
+  }
+
+  #[test]
+  fn application_is_as_ref_calculator() {
+    fn add_through<C: AsRef<dyn I32Calculator>>(calculator: &C, x: i32, y: i32) -> i32 {
+      calculator.as_ref().add(x, y)
+    }
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(5);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(add_through(&app, 2, 3), 5);
+  }
+
+  #[test]
+  fn divmod_returns_quotient_and_remainder() {
+    assert_eq!(IdentityCalculator.divmod(17, 5), Ok((3, 2)));
+    assert_eq!(IdentityCalculator.divmod(-17, 5), Ok((-3, -2)));
+    assert_eq!(
+      IdentityCalculator.divmod(1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+  }
+
+  #[cfg(feature = "real-impl")]
+  #[test]
+  fn external_calculator_computes_divmod() {
+    assert_eq!(ExternalI32Calculator.divmod(17, 5), Ok((3, 2)));
+    assert_eq!(ExternalI32Calculator.divmod(-17, 5), Ok((-3, -2)));
+    assert_eq!(
+      ExternalI32Calculator.divmod(1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+    assert_eq!(
+      ExternalI32Calculator.divmod(i32::MIN, -1),
+      Err(CalculatorError::Overflow {
+        op: "divide",
+        x: i32::MIN,
+        y: -1,
+      })
+    );
+  }
+
+  #[test]
+  fn lru_caching_calculator_evicts_the_least_recently_used_result() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(1), predicate::eq(1))
+      .return_const(2);
+    // evicted once, so calculated twice
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .with(predicate::eq(2), predicate::eq(2))
+      .return_const(4);
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(3))
+      .return_const(6);
+
+    let calculator = LruCachingCalculator::new(mock_i32_calculator, 2);
+    assert_eq!(calculator.add(1, 1), 2);
+    assert_eq!(calculator.add(2, 2), 4);
+    assert_eq!(calculator.len(), 2);
+
+    // makes 1 + 1 more recent than 2 + 2
+    assert_eq!(calculator.add(1, 1), 2);
+    assert_eq!(calculator.add(3, 3), 6);
+    assert_eq!(calculator.len(), 2);
+
+    assert_eq!(calculator.add(1, 1), 2);
+    assert_eq!(calculator.add(2, 2), 4);
+  }
+
+  #[test]
+  fn cool_algorithm_returns_the_constant_divide_result() {
+    let app = Application::new(Box::new(ConstantCalculator::new(1, 2, 3, 4)));
+    assert_eq!(app.cool_algorithm(100), 4);
+
+    assert_eq!(
+      ConstantCalculator::from([1, 2, 3, 4]),
+      ConstantCalculator::new(1, 2, 3, 4)
+    );
+  }
+
+  #[test]
+  fn checked_add_cf_breaks_out_of_try_fold_on_overflow() {
+    let calculator = CountingCalculator::new(IdentityCalculator);
+
+    assert_eq!(
+      [1, 2, 3]
+        .iter()
+        .try_fold(0, |sum, &value| calculator.checked_add_cf(sum, value)),
+      core::ops::ControlFlow::Continue(6)
+    );
+    assert_eq!(
+      [1, i32::MAX, 3]
+        .iter()
+        .try_fold(0, |sum, &value| calculator.checked_add_cf(sum, value)),
+      core::ops::ControlFlow::Break(CalculatorError::Overflow {
+        op: "add",
+        x: 1,
+        y: i32::MAX
+      })
+    );
+    // the overflowing add and everything after it never reached the calculator
+    assert_eq!(calculator.stats().add, 4);
+  }
+
+  #[test]
+  fn enabled_features_reports_compiled_features() {
+    let features = enabled_features();
+
+    assert!(features.contains(&"std"));
+    assert_eq!(features.contains(&"serde"), cfg!(feature = "serde"));
+    assert_eq!(features.contains(&"async"), cfg!(feature = "async"));
+  }
+
+  #[test]
+  fn middleware_nests_in_order() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Doubling;
+    impl CalculatorMiddleware for Doubling {
+      fn wrap(&self, operation: Operation, next: &dyn Fn(Operation) -> i32) -> i32 {
+        next(operation) * 2
+      }
+    }
+
+    struct Logging(Rc<RefCell<Vec<(Operation, i32)>>>);
+    impl CalculatorMiddleware for Logging {
+      fn wrap(&self, operation: Operation, next: &dyn Fn(Operation) -> i32) -> i32 {
+        let result = next(operation);
+        self.0.borrow_mut().push((operation, result));
+        result
+      }
+    }
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(5);
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let calculator = MiddlewareCalculator::new(
+      mock_i32_calculator,
+      vec![Box::new(Logging(log.clone())), Box::new(Doubling)],
+    );
+
+    assert_eq!(calculator.add(2, 3), 10);
+    // the outer logging middleware sees the result of the inner doubling middleware
+    assert_eq!(*log.borrow(), vec![(Operation::Add(2, 3), 10)]);
+  }
+
+  #[cfg(all(
+    feature = "proptest",
+    feature = "real-impl",
+    not(target_arch = "wasm32")
+  ))]
+  proptest::proptest! {
+    #[test]
+    fn cool_algorithm_is_the_identity(x: i32) {
+      let app = Application::new(Box::new(ExternalI32Calculator));
+      proptest::prop_assert_eq!(app.cool_algorithm(x), x);
+    }
+  }
+
+  #[test]
+  fn checked_power_errors_only_when_the_result_overflows() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.checked_power(2, 30), Ok(1 << 30));
+    assert_eq!(
+      app.checked_power(2, 31),
+      Err(CalculatorError::Overflow {
+        op: "power",
+        x: 2,
+        y: 31
+      })
+    );
+    assert_eq!(app.checked_power(-2, 31), Ok(i32::MIN));
+    assert_eq!(app.checked_power(-1, u32::MAX), Ok(-1));
+    assert_eq!(app.checked_power(7, 0), Ok(1));
+  }
+
+  #[test]
+  fn checked_power_squares_through_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    for (x, y) in [(1, 3), (3, 3), (9, 9), (3, 81)] {
+      mock_i32_calculator
+        .expect_checked_multiply()
+        .with(predicate::eq(x), predicate::eq(y))
+        .times(1)
+        .in_sequence(&mut sequence)
+        .returning(|x, y| Ok(x * y));
+    }
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    // 3^5 = 3^1 * 3^4
+    assert_eq!(app.checked_power(3, 5), Ok(243));
+  }
+
+  #[test]
+  fn eval_rpn_dispatches_operators_through_the_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(4))
+      .return_const(Ok(7));
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .times(1)
+      .with(predicate::eq(7), predicate::eq(5))
+      .return_const(Ok(35));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(
+      app.eval_rpn(&[
+        RpnToken::Num(3),
+        RpnToken::Num(4),
+        RpnToken::Op('+'),
+        RpnToken::Num(5),
+        RpnToken::Op('*'),
+      ]),
+      Ok(35)
+    );
+  }
+
+  #[test]
+  fn eval_rpn_rejects_malformed_expressions() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(
+      app.eval_rpn(&[RpnToken::Num(3), RpnToken::Op('+')]),
+      Err(RpnError::StackUnderflow)
+    );
+    assert_eq!(app.eval_rpn(&[]), Err(RpnError::StackUnderflow));
+    assert_eq!(
+      app.eval_rpn(&[RpnToken::Num(3), RpnToken::Num(4)]),
+      Err(RpnError::LeftoverOperands { count: 2 })
+    );
+    assert_eq!(
+      app.eval_rpn(&[RpnToken::Num(3), RpnToken::Num(4), RpnToken::Op('%')]),
+      Err(RpnError::UnknownOperator { op: '%' })
+    );
+  }
+
+  #[test]
+  fn counting_calculator_releases_its_calculator() {
+    test_support::assert_no_leak(CountingCalculator::new);
+  }
+
+  #[test]
+  #[should_panic(expected = "the calculator outlived the value built from it")]
+  fn assert_no_leak_detects_leaks() {
+    test_support::assert_no_leak(core::mem::forget);
+  }
+
+  #[test]
+  fn checked_methods_reject_invalid_operands() {
+    struct NonNegativeCalculator;
+    impl I32Calculator for NonNegativeCalculator {
+      fn validate(&self, x: i32, y: i32) -> Result<(), CalculatorError> {
+        if x < 0 || y < 0 {
+          return Err(CalculatorError::InvalidOperands { x, y });
+        }
+        Ok(())
+      }
+
+      fn add(&self, x: i32, y: i32) -> i32 {
+        x + y
+      }
+
+      fn subtract(&self, x: i32, y: i32) -> i32 {
+        x - y
+      }
+
+      fn multiply(&self, x: i32, y: i32) -> i32 {
+        x * y
+      }
+
+      fn divide(&self, x: i32, y: i32) -> i32 {
+        x / y
+      }
+    }
+
+    assert_eq!(NonNegativeCalculator.checked_add(1, 2), Ok(3));
+    assert_eq!(
+      NonNegativeCalculator.checked_add(-1, 2),
+      Err(CalculatorError::InvalidOperands { x: -1, y: 2 })
+    );
+    // validated before the zero divisor is noticed
+    assert_eq!(
+      NonNegativeCalculator.checked_divide(-1, 0),
+      Err(CalculatorError::InvalidOperands { x: -1, y: 0 })
+    );
+  }
+
+  #[test]
+  fn flaky_calculator_fails_reproducibly() {
+    let calculator = FlakyCalculator::new(IdentityCalculator, 0.3, 42);
+    let results: Vec<_> = (0..10).map(|i| calculator.checked_add(i, 1)).collect();
+
+    for (i, result) in (0..).zip(results) {
+      if [1, 2, 4, 6].contains(&i) {
+        assert_eq!(result, Err(CalculatorError::Transient));
+      } else {
+        assert_eq!(result, Ok(i + 1));
+      }
+    }
+  }
+  #[test]
+  fn flaky_calculator_fails_every_fallible_method() {
+    let calculator = FlakyCalculator::new(IdentityCalculator, 1.0, 42);
+
+    assert_eq!(
+      calculator.checked_modulo(7, 3),
+      Err(CalculatorError::Transient)
+    );
+    assert_eq!(calculator.divmod(7, 3), Err(CalculatorError::Transient));
+    assert_eq!(
+      calculator.multiply_add(2, 3, 4),
+      Err(CalculatorError::Transient)
+    );
+    assert_eq!(calculator.add_i64(1, 2), 3);
+    assert_eq!(calculator.backend_name(), "identity");
+
+    let calculator = FlakyCalculator::new(IdentityCalculator, 0.0, 42);
+
+    assert_eq!(calculator.checked_modulo(7, 3), Ok(1));
+    assert_eq!(calculator.divmod(7, 3), Ok((2, 1)));
+    assert_eq!(calculator.multiply_add(2, 3, 4), Ok(10));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn operation_log_round_trips_through_a_file() {
+    let mut log = OperationLog::new();
+    log.append(Operation::Add(1, 2), 3);
+    log.append(Operation::Divide(8, 2), 4);
+
+    let path = std::env::temp_dir().join(format!(
+      "rust-mock-operation-log-{}.json",
+      std::process::id()
+    ));
+    log.save(&path).unwrap();
+    let loaded = OperationLog::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), log);
+  }
+
+  #[test]
+  fn rolling_apply_reduces_each_window() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    // starting from its first value, each of the 3 windows takes 1 add
+    mock_i32_calculator
+      .expect_add()
+      .times(3)
+      .returning(|x, y| x + y);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(
+      app.rolling_apply(&[1, 2, 3, 4], 2, OpKind::Add),
+      Ok(vec![3, 5, 7])
+    );
+    assert_eq!(app.rolling_apply(&[1, 2, 3, 4], 5, OpKind::Add), Ok(vec![]));
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(
+      app.rolling_apply(&[10, 2, 1, 5], 2, OpKind::Subtract),
+      Ok(vec![8, 1, -4])
+    );
+    assert_eq!(
+      app.rolling_apply(&[100, 10, 2], 2, OpKind::Divide),
+      Ok(vec![10, 5])
+    );
+  }
+
+  #[test]
+  fn rolling_apply_rejects_empty_windows() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(
+      app.rolling_apply(&[1, 2, 3, 4], 0, OpKind::Add),
+      Err(EmptyWindow)
+    );
+  }
+
+  #[test]
+  fn calculator_registry_looks_up_calculators_by_id() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3))
+      .return_const(5);
+
+    let mut registry = CalculatorRegistry::new();
+    assert!(registry
+      .register("mock", Box::new(mock_i32_calculator))
+      .is_none());
+    assert!(registry
+      .register("constant", Box::new(ConstantCalculator::new(1, 2, 3, 4)))
+      .is_none());
+
+    assert_eq!(
+      registry
+        .get("constant")
+        .map(|calculator| calculator.divide(8, 2)),
+      Some(4)
+    );
+    assert!(registry.get("unknown").is_none());
+
+    let app = Application::from_registry(&mut registry, "mock").unwrap();
+    assert_eq!(app.i32_calculator.add(2, 3), 5);
+    assert!(registry.get("mock").is_none());
+  }
+
+  #[test]
+  fn fold_constant_computes_results_directly() {
+    assert_eq!(Operation::Add(2, 3).fold_constant(), Some(5));
+    assert_eq!(Operation::Divide(7, -2).fold_constant(), Some(-3));
+    assert_eq!(Operation::Divide(1, 0).fold_constant(), None);
+    assert_eq!(Operation::Multiply(i32::MAX, 2).fold_constant(), None);
+  }
+
+  #[test]
+  fn subtract_all_reports_the_index_that_underflows() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.subtract_all(10, &[1, 2, 3]), Ok(4));
+    assert_eq!(
+      app.subtract_all(i32::MIN + 5, &[2, 3, 1, 4]),
+      Err((
+        2,
+        CalculatorError::Overflow {
+          op: "subtract",
+          x: i32::MIN,
+          y: 1
+        }
+      ))
+    );
+  }
+
+  #[test]
+  fn cool_algorithm_notifies_live_observers() {
+    let mut live_observer = MockResultObserver::new();
+
+    live_observer
+      .expect_notify()
+      .times(1)
+      .with(predicate::eq(5), predicate::eq(5))
+      .return_const(());
+
+    let live_observer: std::sync::Arc<dyn ResultObserver> = std::sync::Arc::new(live_observer);
+    let dropped_observer: std::sync::Arc<dyn ResultObserver> =
+      std::sync::Arc::new(MockResultObserver::new());
+
+    let mut app = Application::new(Box::new(IdentityCalculator));
+    app.subscribe(std::sync::Arc::downgrade(&live_observer));
+    app.subscribe(std::sync::Arc::downgrade(&dropped_observer));
+    drop(dropped_observer);
+    assert_eq!(app.observer_count(), 2);
+
+    assert_eq!(app.cool_algorithm(5), 5);
+    assert_eq!(app.observer_count(), 1);
+  }
+
+  #[test]
+  fn application_notifies_successful_checked_results() {
+    let mut observer = MockResultObserver::new();
+
+    observer
+      .expect_notify()
+      .times(2)
+      .with(predicate::eq(5), predicate::eq(5))
+      .return_const(());
+
+    let observer: std::sync::Arc<dyn ResultObserver> = std::sync::Arc::new(observer);
+    let mut app = Application::new(Box::new(IdentityCalculator)).with_overflow_guard(true);
+    app.subscribe(std::sync::Arc::downgrade(&observer));
+
+    assert_eq!(app.checked_cool_algorithm(5), Ok(5));
+    assert_eq!(app.guarded_cool_algorithm(5), Ok(5));
+
+    // failures aren't reported
+    let mut failing = Application::new(Box::new(NonNegativeCalculator::new(IdentityCalculator)));
+    failing.subscribe(std::sync::Arc::downgrade(&observer));
+    assert!(failing.checked_cool_algorithm(-5).is_err());
+  }
+
+  #[test]
+  fn application_can_be_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Application<IdentityCalculator>>();
+    assert_send_sync::<Application<dyn SharedCalculator>>();
+  }
+
+  #[test]
+  fn u8_calculator_saturates_by_default() {
+    assert_eq!(ExternalU8Calculator.add(200, 100), u8::MAX);
+    assert_eq!(ExternalU8Calculator.subtract(100, 200), 0);
+    assert_eq!(ExternalU8Calculator.multiply(16, 16), u8::MAX);
+    assert_eq!(ExternalU8Calculator.divide(200, 3), 66);
+
+    let mut mock_u8_calculator = MockU8Calculator::new();
+
+    mock_u8_calculator
+      .expect_add()
+      .times(1)
+      .with(predicate::eq(200), predicate::eq(100))
+      .return_const(44);
+
+    // mocks replace the saturating default
+    assert_eq!(mock_u8_calculator.add(200, 100), 44);
+  }
+
+  #[test]
+  fn coalesced_operations_are_equivalent() {
+    let samples = [-7, 0, 1, 3, 1000];
+    let coalesced = coalesce(&[Operation::Multiply(0, 1), Operation::Multiply(0, 2)]);
+
+    assert_eq!(coalesced.len(), 1);
+    assert!(Operation::Multiply(0, 2).equivalent_to(&coalesced[0], &IdentityCalculator, &samples));
+    assert!(!Operation::Multiply(0, 2).equivalent_to(
+      &Operation::Add(0, 2),
+      &IdentityCalculator,
+      &samples
+    ));
+    // both double 2, so a single sample can't tell them apart
+    assert!(Operation::Multiply(0, 2).equivalent_to(
+      &Operation::Add(0, 2),
+      &IdentityCalculator,
+      &[2]
+    ));
+  }
+
+  #[test]
+  fn verify_on_drop_accepts_the_expected_calls() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator.expect_add().returning(|x, y| x + y);
+
+    let calculator = test_support::VerifyOnDrop::new(mock_i32_calculator, 2);
+    assert_eq!(calculator.add(1, 2), 3);
+    assert_eq!(calculator.add(3, 4), 7);
+  }
+
+  #[test]
+  #[should_panic(expected = "the calculator was called 1 times, expected 2")]
+  fn verify_on_drop_panics_on_missing_calls() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    // no `times`, so the mock itself is happy with a single call
+    mock_i32_calculator.expect_add().returning(|x, y| x + y);
+
+    let calculator = test_support::VerifyOnDrop::new(mock_i32_calculator, 2);
+    calculator.add(1, 2);
+  }
+
+  #[test]
+  fn cool_algorithm_with_uses_custom_operands() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .with(predicate::eq(10), predicate::eq(5))
+      .return_const(Ok(15));
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .times(1)
+      .with(predicate::eq(15), predicate::eq(0))
+      .return_const(Ok(15));
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .times(1)
+      .with(predicate::eq(15), predicate::eq(2))
+      .return_const(Ok(30));
+    mock_i32_calculator
+      .expect_checked_divide()
+      .times(1)
+      .with(predicate::eq(30), predicate::eq(1))
+      .return_const(Ok(30));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+    let params = CoolParams {
+      add: 5,
+      multiply: 2,
+      ..CoolParams::default()
+    };
+
+    assert_eq!(app.cool_algorithm_with(10, params), Ok(30));
+  }
+
+  #[test]
+  fn evaluate_arena_evaluates_left_before_right() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    for (x, y, sum) in [(1, 2, 3), (3, 4, 7)] {
+      mock_i32_calculator
+        .expect_add()
+        .times(1)
+        .with(predicate::eq(x), predicate::eq(y))
+        .in_sequence(&mut sequence)
+        .return_const(sum);
+    }
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(7))
+      .in_sequence(&mut sequence)
+      .return_const(21);
+
+    // (1 + 2) * (3 + 4)
+    let mut arena = ExprArena::new();
+    let [one, two, three, four] = [1, 2, 3, 4].map(|value| arena.push(ExprNode::Literal(value)));
+    let left = arena.push(ExprNode::Add(one, two));
+    let right = arena.push(ExprNode::Add(three, four));
+    let root = arena.push(ExprNode::Multiply(left, right));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.evaluate_arena(&arena, root), 21);
+  }
+
+  #[test]
+  fn budgeted_calculator_stops_calling_once_exhausted() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(3)
+      .returning(|x, y| Ok(x + y));
+
+    let calculator = BudgetedCalculator::new(mock_i32_calculator, 3);
+    for i in 0..3 {
+      assert_eq!(calculator.checked_add(i, 1), Ok(i + 1));
+    }
+    assert_eq!(calculator.remaining(), 0);
+    assert_eq!(
+      calculator.checked_add(3, 1),
+      Err(CalculatorError::BudgetExhausted)
+    );
+  }
+  #[test]
+  fn budgeted_calculator_spends_one_unit_per_modulo() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_modulo()
+      .times(1)
+      .returning(|x, y| Ok(x % y));
+    mock_i32_calculator
+      .expect_divmod()
+      .times(1)
+      .returning(|x, y| Ok((x / y, x % y)));
+    mock_i32_calculator
+      .expect_backend_name()
+      .return_const("mock");
+
+    let calculator = BudgetedCalculator::new(mock_i32_calculator, 2);
+
+    assert_eq!(calculator.checked_modulo(7, 3), Ok(1));
+    assert_eq!(calculator.remaining(), 1);
+    assert_eq!(calculator.divmod(7, 3), Ok((2, 1)));
+    assert_eq!(calculator.remaining(), 0);
+    assert_eq!(
+      calculator.checked_modulo(7, 3),
+      Err(CalculatorError::BudgetExhausted)
+    );
+    assert_eq!(
+      calculator.divmod(7, 3),
+      Err(CalculatorError::BudgetExhausted)
+    );
+    assert_eq!(calculator.backend_name(), "mock");
+  }
+
+  #[test]
+  fn operations_round_trip_through_expr() {
+    let expr = Expr::from(Operation::Add(2, 3));
+
+    assert_eq!(
+      expr,
+      Expr::Add(Box::new(Expr::Literal(2)), Box::new(Expr::Literal(3)))
+    );
+    assert_eq!(Operation::try_from(&expr), Ok(Operation::Add(2, 3)));
+
+    assert_eq!(Operation::try_from(&Expr::Literal(2)), Err(NotAnOperation));
+    assert_eq!(
+      Operation::try_from(&Expr::Multiply(Box::new(expr), Box::new(Expr::Literal(4)))),
+      Err(NotAnOperation)
+    );
+  }
+
+  #[test]
+  fn checksum_is_stable_and_order_sensitive() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.checksum(&[]), 17);
+    assert_eq!(app.checksum(&[1, 2, 3]), 507_473);
+    assert_eq!(app.checksum(&[3, 2, 1]), 509_393);
+  }
+
+  #[test]
+  fn calculators_can_report_unsupported_operations() {
+    struct NoDivideCalculator;
+    impl I32Calculator for NoDivideCalculator {
+      fn supports(&self, op_kind: OpKind) -> bool {
+        op_kind != OpKind::Divide
+      }
+
+      fn add(&self, x: i32, y: i32) -> i32 {
+        x + y
+      }
+
+      fn subtract(&self, x: i32, y: i32) -> i32 {
+        x - y
+      }
+
+      fn multiply(&self, x: i32, y: i32) -> i32 {
+        x * y
+      }
+
+      fn divide(&self, _x: i32, _y: i32) -> i32 {
+        panic!("divide is unsupported and must not be called")
+      }
+    }
+
+    assert!(NoDivideCalculator.supports(OpKind::Add));
+    assert!(!NoDivideCalculator.supports(OpKind::Divide));
+    assert!(IdentityCalculator.supports(OpKind::Divide));
+  }
+
+  #[test]
+  fn cool_algorithm_trace_renders_each_step() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator.expect_add().return_const(101);
+    mock_i32_calculator.expect_subtract().return_const(102);
+    mock_i32_calculator.expect_multiply().return_const(103);
+    mock_i32_calculator.expect_divide().return_const(104);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+    let (result, trace) = app.cool_algorithm_traced(100);
+
+    assert_eq!(result, 104);
+    insta::assert_snapshot!(trace, @r"
+    100 + 0 = 101
+    101 - 0 = 102
+    102 * 1 = 103
+    103 / 1 = 104
+    ");
+  }
+
+  #[test]
+  fn compiled_pipeline_runs_on_each_input() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(3)
+      .returning(|x, y| x + y);
+    mock_i32_calculator
+      .expect_multiply()
+      .times(3)
+      .returning(|x, y| x * y);
+
+    let pipeline = compile_pipeline(&[Operation::Add(0, 1), Operation::Multiply(0, 10)]);
+    let results: Vec<i32> = [1, 2, 3]
+      .iter()
+      .map(|&input| pipeline.run(&mock_i32_calculator, input))
+      .collect();
+
+    assert_eq!(results, vec![20, 30, 40]);
+  }
+  #[test]
+  fn compiled_pipeline_runs_long_pipelines() {
+    let pipeline = compile_pipeline(&vec![Operation::Add(0, 1); 100_000]);
+
+    assert_eq!(pipeline.run(&IdentityCalculator, 0), 100_000);
+  }
+
+  #[cfg(feature = "real-impl")]
+  #[test]
+  fn application_from_config_saturates_real_backend() {
+    let application = Application::try_from(CalculatorConfig {
+      backend: CalculatorBackend::External,
+      overflow_policy: OverflowPolicy::Saturate,
+      bounds: Some((-100, 100)),
+    })
+    .unwrap();
+
+    assert_eq!(application.i32_calculator.add(60, 60), 100);
+    assert_eq!(application.i32_calculator.subtract(-60, 60), -100);
+    assert_eq!(application.i32_calculator.multiply(6, 7), 42);
+  }
+
+  #[test]
+  fn application_from_config_rejects_invalid_bounds() {
+    let inverted = Application::try_from(CalculatorConfig {
+      bounds: Some((10, -10)),
+      ..CalculatorConfig::default()
+    });
+
+    assert!(matches!(
+      inverted,
+      Err(ConfigError::InvertedBounds { min: 10, max: -10 })
+    ));
+  }
+
+  #[test]
+  fn table_calculator_only_delegates_outside_of_table() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .with(predicate::eq(16), predicate::eq(1))
+      .times(1)
+      .returning(|x, y| x + y);
+    mock_i32_calculator
+      .expect_multiply()
+      .with(predicate::eq(-2), predicate::eq(3))
+      .times(1)
+      .returning(|x, y| x * y);
+
+    let table_calculator = TableCalculator::new(mock_i32_calculator);
+
+    assert_eq!(table_calculator.add(15, 15), 30);
+    assert_eq!(table_calculator.subtract(3, 9), -6);
+    assert_eq!(table_calculator.multiply(7, 8), 56);
+    assert_eq!(table_calculator.divide(15, 4), 3);
+    assert_eq!(table_calculator.add(16, 1), 17);
+    assert_eq!(table_calculator.multiply(-2, 3), -6);
+  }
+
+  #[test]
+  fn divide_exact_reports_remainder() {
+    assert_eq!(
+      IdentityCalculator.divide_exact(6, 3),
+      Ok(DivResult {
+        quotient: 2,
+        exact: true,
+      })
+    );
+    assert_eq!(
+      IdentityCalculator.divide_exact(7, 3),
+      Ok(DivResult {
+        quotient: 2,
+        exact: false,
+      })
+    );
+    assert_eq!(
+      IdentityCalculator.divide_exact(7, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 7 })
+    );
+  }
+
+  #[test]
+  fn compare_workload_lists_disagreements() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator.expect_add().returning(|x, y| x + y);
+    mock_i32_calculator
+      .expect_subtract()
+      .returning(|x, y| x - y);
+    mock_i32_calculator
+      .expect_multiply()
+      .returning(|x, y| x * y);
+    mock_i32_calculator
+      .expect_divide()
+      .returning(|x, y| if x % 2 == 0 { -x } else { x / y });
+
+    let identity_app = Application::new(Box::new(IdentityCalculator));
+    let wrong_app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(
+      identity_app.compare_workload(&wrong_app, &[1, 2, 3, 4]),
+      vec![(2, 2, -2), (4, 4, -4)]
+    );
+  }
+
+  #[test]
+  fn cool_algorithm_bench_matches_cool_algorithm() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    for x in [i32::MIN, -7, 0, 1, 42, i32::MAX] {
+      assert_eq!(app.cool_algorithm_bench(x), app.cool_algorithm(x));
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn timing_calculator_reports_median_delay() {
+    let delay = std::time::Duration::from_millis(10);
+    let timing_calculator = TimingCalculator::new(DelayCalculator::new(IdentityCalculator, delay));
+
+    for x in 0..5 {
+      timing_calculator.add(x, 1);
+    }
+    let median = timing_calculator.percentile(OpKind::Add, 50.0).unwrap();
+
+    assert!(median >= delay);
+    assert!(median < delay * 5);
+    assert_eq!(timing_calculator.percentile(OpKind::Divide, 50.0), None);
+    assert_eq!(timing_calculator.percentile(OpKind::Add, 101.0), None);
+
+    let timing_calculator = TimingCalculator::new(IdentityCalculator);
+
+    assert_eq!(timing_calculator.checked_modulo(7, 3), Ok(1));
+    assert_eq!(timing_calculator.divmod(7, 3), Ok((2, 1)));
+    assert!(timing_calculator
+      .percentile(OpKind::Divide, 100.0)
+      .is_some());
+    assert_eq!(timing_calculator.percentile(OpKind::Multiply, 100.0), None);
+    assert_eq!(timing_calculator.backend_name(), "identity");
+  }
+
+  #[test]
+  fn calc_value_operators_use_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_multiply()
+      .with(predicate::eq(3), predicate::eq(2))
+      .times(1)
+      .returning(|x, y| x * y);
+    mock_i32_calculator
+      .expect_add()
+      .with(predicate::eq(6), predicate::eq(1))
+      .times(1)
+      .returning(|x, y| x + y);
+
+    let v = CalcValue::new(&mock_i32_calculator, 3);
+
+    assert_eq!((v * 2 + 1).value(), 7);
+  }
+
+  #[test]
+  fn reduce_scanned_returns_running_results() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(|x, y| x + y);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.reduce_scanned(&[1, 2, 3], OpKind::Add), vec![1, 3, 6]);
+    assert!(app.reduce_scanned(&[], OpKind::Add).is_empty());
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(
+      app.reduce_scanned(&[10, 2, 3], OpKind::Subtract),
+      vec![10, 8, 5]
+    );
+    assert_eq!(
+      app.reduce_scanned(&[100, 2, 5], OpKind::Divide),
+      vec![100, 50, 10]
+    );
+  }
+
+  #[test]
+  fn plugin_calculator_calls_vtable() {
+    extern "C" fn add(x: i32, y: i32) -> i32 {
+      x.wrapping_add(y)
+    }
+    extern "C" fn subtract(x: i32, y: i32) -> i32 {
+      x.wrapping_sub(y)
+    }
+    extern "C" fn multiply(x: i32, y: i32) -> i32 {
+      x.wrapping_mul(y)
+    }
+    extern "C" fn divide(x: i32, y: i32) -> i32 {
+      x.wrapping_div(y)
+    }
+
+    let plugin_calculator = PluginCalculator::new(PluginVTable {
+      add,
+      subtract,
+      multiply,
+      divide,
+    });
+    let app = Application::new(Box::new(plugin_calculator));
+
+    assert_eq!(app.cool_algorithm(42), 42);
+    assert_eq!(app.i32_calculator.multiply(6, 7), 42);
+  }
+
+  #[test]
+  fn lerp_interpolates_through_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .with(predicate::eq(10), predicate::eq(0))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x - y));
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .with(predicate::eq(10), predicate::eq(1))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x * y));
+    mock_i32_calculator
+      .expect_checked_divide()
+      .with(predicate::eq(10), predicate::eq(2))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x / y));
+    mock_i32_calculator
+      .expect_checked_add()
+      .with(predicate::eq(0), predicate::eq(5))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x + y));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.lerp(0, 10, 1, 2), Ok(5));
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(
+      app.lerp(0, 10, 1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 10 })
+    );
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test(start_paused = true)]
+  async fn cancellable_calculator_returns_cancelled() {
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_add()
+      .times(1)
+      .returning(|x, y| {
+        Box::pin(async move {
+          tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+          Ok(x + y)
+        })
+      });
+
+    let token = tokio_util::sync::CancellationToken::new();
+    let calculator = CancellableCalculator::new(mock_async_i32_calculator, token.clone());
+
+    let canceller = async {
+      tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+      token.cancel();
+    };
+    let (result, ()) = futures::join!(calculator.add(2, 3), canceller);
+
+    assert_eq!(result, Err(CalculatorError::Cancelled));
+    // already cancelled, so the wrapped calculator isn't called again
+    assert_eq!(calculator.add(2, 3).await, Err(CalculatorError::Cancelled));
+  }
+
+  #[test]
+  fn multiply_add_goes_through_checked_methods() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .with(predicate::eq(6), predicate::eq(7))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x * y));
+    mock_i32_calculator
+      .expect_checked_add()
+      .with(predicate::eq(42), predicate::eq(-2))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x + y));
+
+    // the mock stubs `multiply_add` itself, so call the default through a decorator that only
+    // forwards the checked methods
+    decorator! {
+      Forwarding,
+      before: |_, _, _, _| {},
+      after: |_, _, result| result,
+    }
+
+    let calculator = Forwarding {
+      i32_calculator: mock_i32_calculator,
+    };
+
+    assert_eq!(calculator.multiply_add(6, 7, -2), Ok(40));
+
+    let calculator = IdentityCalculator;
+
+    assert_eq!(
+      calculator.multiply_add(i32::MAX, 2, -i32::MAX),
+      Err(CalculatorError::Overflow {
+        op: "multiply",
+        x: i32::MAX,
+        y: 2,
+      })
+    );
+    assert_eq!(
+      calculator.multiply_add(i32::MAX, 1, 1),
+      Err(CalculatorError::Overflow {
+        op: "add",
+        x: i32::MAX,
+        y: 1,
+      })
+    );
+    assert_eq!(calculator.multiply_add(i32::MIN, 1, i32::MAX), Ok(-1));
+  }
+
+  #[test]
+  fn cool_algorithm_does_nothing_with_expect_calculator() {
+    let number = 100;
+
+    let mock_i32_calculator = test_support::ExpectCalculator::new()
+      .add(number, 0, number)
+      .subtract(number, 0, number)
+      .multiply(number, 1, number)
+      .divide(number, 1, number)
+      .build();
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.cool_algorithm(number), number);
+  }
+
+  #[test]
+  fn checked_running_sum_stops_at_overflow() {
+    let values = [i32::MAX - 3, 2, 2, 5];
+    let mut sums = IdentityCalculator.checked_running_sum(&values);
+
+    assert_eq!(sums.next(), Some(Ok(i32::MAX - 3)));
+    assert_eq!(sums.next(), Some(Ok(i32::MAX - 1)));
+    assert_eq!(
+      sums.next(),
+      Some(Err(CalculatorError::Overflow {
+        op: "add",
+        x: i32::MAX - 1,
+        y: 2,
+      }))
+    );
+    assert_eq!(sums.next(), None);
+  }
+
+  #[test]
+  fn median_averages_middle_values_with_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_subtract()
+      .with(predicate::eq(3), predicate::eq(2))
+      .times(1)
+      .return_const(1);
+    mock_i32_calculator
+      .expect_divide()
+      .with(predicate::eq(1), predicate::eq(2))
+      .times(1)
+      .return_const(0);
+    mock_i32_calculator
+      .expect_add()
+      .with(predicate::eq(2), predicate::eq(0))
+      .times(1)
+      .return_const(2);
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.median(&[4, 1, 3, 2]), Some(2));
+    assert_eq!(app.median(&[5, 1, 3]), Some(3));
+    assert_eq!(app.median(&[]), None);
+  }
+
+  #[test]
+  fn median_of_large_values_does_not_overflow() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.median(&[i32::MAX, i32::MAX]), Some(i32::MAX));
+    assert_eq!(app.median(&[i32::MIN, i32::MIN]), Some(i32::MIN));
+    assert_eq!(app.median(&[i32::MIN, i32::MAX]), Some(0));
+    assert_eq!(app.median(&[i32::MAX - 1, i32::MAX]), Some(i32::MAX - 1));
+    // rounds toward zero like `(-3 + -2) / 2`
+    assert_eq!(app.median(&[-3, -2]), Some(-2));
+  }
+
+  #[test]
+  fn running_stats_mean_divides_sum_by_count() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(3)
+      .returning(|x, y| x + y);
+    mock_i32_calculator
+      .expect_divide()
+      .with(predicate::eq(12), predicate::eq(3))
+      .times(1)
+      .return_const(4);
+
+    let mut stats = RunningStats::new();
+
+    assert_eq!(stats.mean(&mock_i32_calculator), None);
+    for x in [2, 4, 6] {
+      stats.observe(x, &mock_i32_calculator);
+    }
+
+    assert_eq!(stats.count(), 3);
+    assert_eq!(stats.sum(), 12);
+    assert_eq!((stats.min(), stats.max()), (Some(2), Some(6)));
+    assert_eq!(stats.mean(&mock_i32_calculator), Some(4));
+  }
+
+  #[test]
+  fn cloned_application_uses_its_own_calculator() {
+    #[derive(Clone, Default)]
+    struct CallCountingCalculator {
+      calls: core::cell::Cell<usize>,
+    }
+    impl I32Calculator for CallCountingCalculator {
+      fn boxed_clone(&self) -> Option<Box<dyn I32Calculator>> {
+        Some(Box::new(self.clone()))
+      }
+
+      fn add(&self, x: i32, y: i32) -> i32 {
+        self.calls.set(self.calls.get() + 1);
+        x + y
+      }
+
+      fn subtract(&self, x: i32, y: i32) -> i32 {
+        self.calls.set(self.calls.get() + 1);
+        x - y
+      }
+
+      fn multiply(&self, x: i32, y: i32) -> i32 {
+        self.calls.set(self.calls.get() + 1);
+        x * y
+      }
+
+      fn divide(&self, x: i32, y: i32) -> i32 {
+        self.calls.set(self.calls.get() + 1);
+        x / y
+      }
+    }
+    let calls = |app: &Application| {
+      app
+        .calculator_as::<CallCountingCalculator>()
+        .unwrap()
+        .calls
+        .get()
+    };
+
+    let app: Application = Application::new(Box::new(CallCountingCalculator::default()));
+    app.cool_algorithm(1);
+    let cloned_app = app.clone();
+    cloned_app.cool_algorithm(2);
+    cloned_app.cool_algorithm(3);
+
+    assert_eq!(calls(&app), 4);
+    assert_eq!(calls(&cloned_app), 12);
+
+    let app: Application = Application::new(Box::new(MonotonicCalculator::new(IdentityCalculator)));
+
+    assert!(app.try_clone().is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "boxed_clone returned None")]
+  fn cloning_application_panics_for_uncloneable_calculator() {
+    let app: Application = Application::new(Box::new(MonotonicCalculator::new(IdentityCalculator)));
+
+    let _ = app.clone();
+  }
+
+  #[test]
+  fn cloned_application_keeps_observers() {
+    let mut observer = MockResultObserver::new();
+
+    observer
+      .expect_notify()
+      .times(2)
+      .with(predicate::eq(5), predicate::eq(5))
+      .return_const(());
+
+    let observer: std::sync::Arc<dyn ResultObserver> = std::sync::Arc::new(observer);
+    let mut app = Application::new(Box::new(IdentityCalculator));
+    app.subscribe(std::sync::Arc::downgrade(&observer));
+    let cloned_app = app.clone();
+
+    assert_eq!(cloned_app.observer_count(), 1);
+    assert_eq!(app.cool_algorithm(5), 5);
+    assert_eq!(cloned_app.cool_algorithm(5), 5);
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test(start_paused = true)]
+  async fn async_retrying_calculator_error_has_source() {
+    use core::error::Error;
+
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_divide()
+      .times(2)
+      .returning(|_, _| Box::pin(async { Err(CalculatorError::Transient) }));
+
+    let calculator = AsyncRetryingCalculator::new(
+      mock_async_i32_calculator,
+      std::time::Duration::from_millis(100),
+      2,
+    );
+    let error = calculator.divide(7, 0).await.unwrap_err();
+
+    assert_eq!(
+      error
+        .source()
+        .and_then(|source| source.downcast_ref::<CalculatorError>()),
+      Some(&CalculatorError::Transient)
+    );
+    assert_eq!(
+      error.to_string(),
+      "wrapped calculator failed: calculator failed temporarily"
+    );
+  }
+
+  #[test]
+  fn non_negative_calculator_rejects_negative_results() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .with(predicate::eq(3), predicate::eq(5))
+      .times(1)
+      .returning(|x, y| Ok(x - y));
+    mock_i32_calculator
+      .expect_checked_add()
+      .with(predicate::eq(3), predicate::eq(5))
+      .times(1)
+      .returning(|x, y| Ok(x + y));
+
+    let non_negative_calculator = NonNegativeCalculator::new(mock_i32_calculator);
+
+    assert_eq!(
+      non_negative_calculator.checked_subtract(3, 5),
+      Err(CalculatorError::NegativeResult)
+    );
+    assert_eq!(non_negative_calculator.checked_add(3, 5), Ok(8));
+
+    let non_negative_calculator = NonNegativeCalculator::new(IdentityCalculator);
+
+    assert_eq!(
+      non_negative_calculator.checked_modulo(-1, 2),
+      Err(CalculatorError::NegativeResult)
+    );
+    assert_eq!(
+      non_negative_calculator.divmod(-7, 2),
+      Err(CalculatorError::NegativeResult)
+    );
+    assert_eq!(
+      non_negative_calculator.divmod(7, -2),
+      Err(CalculatorError::NegativeResult)
+    );
+    assert_eq!(non_negative_calculator.divmod(7, 2), Ok((3, 1)));
+    assert_eq!(non_negative_calculator.backend_name(), "identity");
+  }
+
+  #[test]
+  fn memoized_application_calls_calculator_once_per_input() {
+    let mock_i32_calculator = test_support::ExpectCalculator::new()
+      .add(7, 0, 7)
+      .subtract(7, 0, 7)
+      .multiply(7, 1, 7)
+      .divide(7, 1, 7)
+      .build();
+    let memoized_app = Application::new(Box::new(mock_i32_calculator)).with_memoization();
+
+    assert_eq!(memoized_app.cool_algorithm(7), 7);
+    assert_eq!(memoized_app.cool_algorithm(7), 7);
+  }
+
+  #[test]
+  fn divide_bankers_rounds_halves_to_even() {
+    let calculator = IdentityCalculator;
+
+    assert_eq!(calculator.divide_bankers(5, 2), Ok(2));
+    assert_eq!(calculator.divide_bankers(7, 2), Ok(4));
+    assert_eq!(calculator.divide_bankers(3, 2), Ok(2));
+    assert_eq!(calculator.divide_bankers(-5, 2), Ok(-2));
+    assert_eq!(calculator.divide_bankers(7, -2), Ok(-4));
+    assert_eq!(calculator.divide_bankers(7, 3), Ok(2));
+    assert_eq!(calculator.divide_bankers(8, 3), Ok(3));
+    assert_eq!(calculator.divide_bankers(i32::MAX, 2), Ok(i32::MAX / 2 + 1));
+    assert_eq!(
+      calculator.divide_bankers(1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+  }
+
+  #[cfg(feature = "async")]
+  #[tokio::test]
+  async fn async_recording_calculator_sends_events_in_order() {
+    let mut mock_async_i32_calculator = MockAsyncI32Calculator::new();
+
+    mock_async_i32_calculator
+      .expect_add()
+      .returning(|x, y| Box::pin(async move { Ok(x + y) }));
+    mock_async_i32_calculator.expect_divide().returning(|x, _| {
+      Box::pin(async move { Err(CalculatorError::DivisionByZero { dividend: x }) })
+    });
+
+    let (calculator, mut events) = AsyncRecordingCalculator::new(mock_async_i32_calculator);
+
+    assert_eq!(calculator.add(2, 3).await, Ok(5));
+    assert!(calculator.divide(5, 0).await.is_err());
+    drop(calculator);
+
+    assert_eq!(events.recv().await, Some((Operation::Add(2, 3), Ok(5))));
+    assert_eq!(
+      events.recv().await,
+      Some((
+        Operation::Divide(5, 0),
+        Err(CalculatorError::DivisionByZero { dividend: 5 })
+      ))
+    );
+    assert_eq!(events.recv().await, None);
+  }
+
+  #[test]
+  fn batch_dedup_performs_repeated_operations_once() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .with(predicate::eq(2), predicate::eq(3))
+      .times(1)
+      .returning(|x, y| x + y);
+    mock_i32_calculator
+      .expect_multiply()
+      .with(predicate::eq(2), predicate::eq(3))
+      .times(1)
+      .returning(|x, y| x * y);
+
+    let results = mock_i32_calculator.batch_dedup(&[
+      Operation::Add(2, 3),
+      Operation::Multiply(2, 3),
+      Operation::Add(2, 3),
+    ]);
+
+    assert_eq!(results, vec![5, 6, 5]);
+  }
+
+  #[cfg(feature = "rayon")]
+  #[test]
+  fn parallel_map_matches_sequential_results() {
+    let app = Application::new(Box::new(IdentityCalculator));
+    let inputs: Vec<i32> = (-5_000..5_000).map(|x| x * 7919).collect();
+
+    let sequential: Vec<i32> = inputs.iter().map(|&x| app.cool_algorithm(x)).collect();
+
+    assert_eq!(app.parallel_map(&inputs), sequential);
+  }
+
+  #[test]
+  fn estimate_folds_constants() {
+    assert_eq!(IdentityCalculator.estimate(&Operation::Add(2, 3)), Some(5));
+    assert_eq!(IdentityCalculator.estimate(&Operation::Divide(1, 0)), None);
+    assert_eq!(
+      IdentityCalculator.estimate(&Operation::Add(i32::MAX, 1)),
+      None
+    );
+    assert_eq!(
+      ConstantCalculator::new(1, 2, 3, 4).estimate(&Operation::Divide(1, 0)),
+      Some(4)
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn registry_from_toml_registers_each_calculator() {
+    let registry = CalculatorRegistry::from_toml_str(
+      r#"
+        [calculators.saturating]
+        backend = "identity"
+        overflow_policy = "saturate"
+        bounds = [-100, 100]
+
+        [calculators.wrapping]
+        backend = "identity"
+        bounds = [0, 9]
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(registry.get("saturating").unwrap().add(60, 60), 100);
+    assert_eq!(registry.get("wrapping").unwrap().add(5, 7), 2);
+
+    let inverted = CalculatorRegistry::from_toml_str(
+      r#"
+        [calculators.inverted]
+        bounds = [10, -10]
+      "#,
+    );
+    assert_eq!(
+      inverted.err().map(|error| error.kind()),
+      Some(std::io::ErrorKind::InvalidInput)
+    );
+  }
+
+  #[test]
+  fn verify_algebraic_laws_reports_first_violation() {
+    let samples = [(1, 2, 3), (-7, 0, i32::MAX), (i32::MIN, -1, 2)];
+
+    assert_eq!(IdentityCalculator.verify_algebraic_laws(&samples), Ok(()));
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(|x, y| 2 * x + y);
+
+    assert_eq!(
+      mock_i32_calculator.verify_algebraic_laws(&samples),
+      Err(LawViolation::AddCommutativity { x: 1, y: 2 })
+    );
+  }
+
+  #[test]
+  fn fixed_point_arithmetic_in_q16() {
+    let (one, half, quarter) = (1 << 16, 1 << 15, 1 << 14);
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .with(predicate::eq(half), predicate::eq(half))
+      .times(1)
+      .returning(|x, y| Ok(x * y));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.fixed_multiply(half, half, 16), Ok(quarter));
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.fixed_divide(quarter, half, 16), Ok(half));
+    assert_eq!(app.fixed_multiply(-half, half, 16), Ok(-quarter));
+    assert_eq!(
+      app.fixed_multiply(half, half, 32),
+      Err(CalculatorError::Overflow {
+        op: "fixed_multiply",
+        x: half,
+        y: half,
+      })
+    );
+    // the raw product of 1.0 and 1.0 in Q16 needs 33 bits
+    assert_eq!(
+      app.fixed_multiply(one, one, 16),
+      Err(CalculatorError::Overflow {
+        op: "fixed_multiply",
+        x: one,
+        y: one,
+      })
+    );
+    // so does 1.0 shifted left by 16 bits
+    assert_eq!(
+      app.fixed_divide(one, half, 16),
+      Err(CalculatorError::Overflow {
+        op: "fixed_divide",
+        x: one,
+        y: half,
+      })
+    );
+    assert_eq!(
+      app.fixed_divide(half, 0, 16),
+      Err(CalculatorError::DivisionByZero { dividend: half })
+    );
+  }
+
+  #[test]
+  fn scaled_pipeline_uses_fixed_point_values() {
+    // Q8, so 1.5 is 384
+    let app = Application::new(Box::new(IdentityCalculator)).scaled(8);
+    let ops = [
+      Operation::Add(0, 128),
+      Operation::Multiply(0, 384),
+      Operation::Divide(0, 512),
+    ];
+
+    // (1.5 + 0.5) * 1.5 / 2.0 = 1.5
+    assert_eq!(app.scaled_pipeline(384, &ops), Ok(384));
+    // without fractional bits the values are plain integers
+    assert_eq!(
+      app
+        .clone()
+        .scaled(0)
+        .scaled_pipeline(3, &[Operation::Multiply(0, 4), Operation::Divide(0, 2)]),
+      Ok(6)
+    );
+    assert_eq!(
+      app.scaled_pipeline(i32::MAX, &ops),
+      Err(CalculatorError::Overflow {
+        op: "add",
+        x: i32::MAX,
+        y: 128,
+      })
+    );
+  }
+
+  #[test]
+  fn application_reports_backend_name() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert_eq!(app.backend(), "identity");
+    assert_eq!(ExternalI32Calculator.backend_name(), "external");
+    assert_eq!(
+      ConstantCalculator::new(1, 2, 3, 4).backend_name(),
+      "unknown"
+    );
+  }
+
+  #[test]
+  fn divergence_detector_reports_nondeterminism() {
+    let mut flaky_calculator = MockI32Calculator::new();
+
+    let mut calls = 0;
+    flaky_calculator
+      .expect_checked_add()
+      .times(2)
+      .returning(move |x, y| {
+        calls += 1;
+        Ok(x + y + calls)
+      });
+
+    let mut deterministic_calculator = MockI32Calculator::new();
+
+    deterministic_calculator
+      .expect_checked_add()
+      .times(2)
+      .returning(|x, y| Ok(x + y));
+
+    assert_eq!(
+      DivergenceDetector::new(flaky_calculator).checked_add(2, 3),
+      Err(CalculatorError::Nondeterministic)
+    );
+    assert_eq!(
+      DivergenceDetector::new(deterministic_calculator).checked_add(2, 3),
+      Ok(5)
+    );
+
+    let mut flaky_calculator = MockI32Calculator::new();
+
+    let mut calls = 0;
+    flaky_calculator
+      .expect_divmod()
+      .times(2)
+      .returning(move |x, y| {
+        calls += 1;
+        Ok((x / y, x % y + calls))
+      });
+    flaky_calculator
+      .expect_checked_modulo()
+      .times(2)
+      .returning(|x, y| Ok(x % y));
+
+    let detector = DivergenceDetector::new(flaky_calculator);
+
+    assert_eq!(
+      detector.divmod(7, 3),
+      Err(CalculatorError::Nondeterministic)
+    );
+    assert_eq!(detector.checked_modulo(7, 3), Ok(1));
+  }
+
+  #[test]
+  #[should_panic(expected = "calculator gave different results")]
+  fn divergence_detector_panics_on_unchecked_nondeterminism() {
+    let mut flaky_calculator = MockI32Calculator::new();
+
+    let mut calls = 0;
+    flaky_calculator.expect_add().returning(move |x, y| {
+      calls += 1;
+      x + y + calls
+    });
+
+    DivergenceDetector::new(flaky_calculator).add(2, 3);
+  }
+
+  #[test]
+  fn describe_plan_lists_folded_steps() {
+    let app = Application::new(Box::new(IdentityCalculator));
+    let plan = app.describe_plan(
+      5,
+      &[
+        Operation::Add(0, 2),
+        Operation::Multiply(0, 3),
+        Operation::Divide(0, 0),
+        Operation::Subtract(0, 1),
+      ],
+    );
+
+    assert_eq!(
+      plan,
+      "step 1: 5 + 2\nstep 2: 7 * 3\nstep 3: 21 / 0\nstep 4: ? - 1"
+    );
+    assert_eq!(app.describe_plan(5, &[]), "");
+  }
+
+  #[test]
+  fn gcd_accumulator_calls_gcd_per_push() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    for (x, y, gcd) in [(0, 12, 12), (12, 8, 4), (4, 20, 4)] {
+      mock_i32_calculator
+        .expect_gcd()
+        .times(1)
+        .with(predicate::eq(x), predicate::eq(y))
+        .in_sequence(&mut sequence)
+        .return_const(gcd);
+    }
+
+    let mut accumulator = GcdAccumulator::new();
+    let currents: Vec<i32> = [12, 8, 20]
+      .iter()
+      .map(|&x| {
+        accumulator.push(x, &mock_i32_calculator);
+        accumulator.current()
+      })
+      .collect();
+
+    assert_eq!(currents, vec![12, 4, 4]);
+  }
+
+  #[test]
+  fn typed_pipeline_runs_added_operations() {
+    let pipeline = TypedPipeline::new()
+      .then(Operation::Add(0, 2))
+      .then(Operation::Multiply(0, 3));
+
+    assert_eq!(pipeline.run(&IdentityCalculator, 5), 21);
+  }
+
+  #[test]
+  fn monotonic_calculator_rejects_decreasing_results() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .returning(|x, y| Ok(x + y));
+
+    let monotonic_calculator = MonotonicCalculator::new(mock_i32_calculator);
+
+    assert_eq!(monotonic_calculator.last(), None);
+    assert_eq!(monotonic_calculator.checked_add(1, 1), Ok(2));
+    assert_eq!(monotonic_calculator.checked_add(2, 0), Ok(2));
+    assert_eq!(monotonic_calculator.checked_add(2, 3), Ok(5));
+    assert_eq!(
+      monotonic_calculator.checked_add(1, 3),
+      Err(CalculatorError::NonMonotonic {
+        previous: 5,
+        result: 4,
+      })
+    );
+    assert_eq!(monotonic_calculator.last(), Some(5));
+    assert_eq!(monotonic_calculator.checked_add(3, 3), Ok(6));
+    assert_eq!(monotonic_calculator.last(), Some(6));
+
+    let monotonic_calculator = MonotonicCalculator::new(IdentityCalculator);
+
+    assert_eq!(monotonic_calculator.multiply_add(2, 3, 1), Ok(7));
+    assert_eq!(
+      monotonic_calculator.checked_modulo(7, 3),
+      Err(CalculatorError::NonMonotonic {
+        previous: 7,
+        result: 1,
+      })
+    );
+    assert_eq!(
+      monotonic_calculator.divmod(14, 3),
+      Err(CalculatorError::NonMonotonic {
+        previous: 7,
+        result: 4,
+      })
+    );
+    assert_eq!(monotonic_calculator.divmod(23, 3), Ok((7, 2)));
+    assert_eq!(monotonic_calculator.last(), Some(7));
+  }
+
+  #[test]
+  fn deadline_calculator_stops_at_deadline() {
+    // no expectations, so any call would panic
+    let expired = DeadlineCalculator::new(MockI32Calculator::new(), Instant::now());
+
+    assert_eq!(
+      expired.checked_add(1, 2),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+    assert_eq!(
+      expired.checked_subtract(1, 2),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+    assert_eq!(
+      expired.checked_multiply(1, 2),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+    assert_eq!(
+      expired.checked_divide(1, 2),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+    assert_eq!(
+      expired.checked_modulo(1, 2),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+    assert_eq!(expired.divmod(1, 2), Err(CalculatorError::DeadlineExceeded));
+    assert_eq!(
+      expired.multiply_add(1, 2, 3),
+      Err(CalculatorError::DeadlineExceeded)
+    );
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .returning(|x, y| Ok(x + y));
+
+    let deadline = Instant::now() + std::time::Duration::from_secs(3600);
+    let calculator = DeadlineCalculator::new(mock_i32_calculator, deadline);
+
+    assert_eq!(calculator.checked_add(1, 2), Ok(3));
+  }
+
+  #[test]
+  fn ratio_combines_quotient_and_remainder() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_divide()
+      .with(predicate::eq(7), predicate::eq(2))
+      .times(1)
+      .returning(|x, y| Ok(x / y));
+    mock_i32_calculator
+      .expect_checked_modulo()
+      .with(predicate::eq(7), predicate::eq(2))
+      .times(1)
+      .returning(|x, y| Ok(x % y));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert!((app.ratio(7, 2).unwrap() - 3.5).abs() < 1e-12);
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    assert!((app.ratio(-1, 3).unwrap() + 1.0 / 3.0).abs() < 1e-12);
+    assert_eq!(
+      app.ratio(7, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 7 })
+    );
+  }
+
+  #[test]
+  fn decorator_macro_calls_hooks_around_operations() {
+    use std::cell::{Cell, RefCell};
+
+    decorator! {
+      Recording { events: RefCell<Vec<String>> },
+      before: |this, op, x, y| this.events.borrow_mut().push(format!("{op:?}({x}, {y})")),
+      after: |this, op, result| {
+        this.events.borrow_mut().push(format!("{op:?} = {result}"));
+        result
+      },
+    }
+
+    decorator! {
+      Scaling { calls: Cell<usize>, factor: i32 },
+      before: |this, _, _, _| this.calls.set(this.calls.get() + 1),
+      after: |this, _, result| result * this.factor,
+    }
+
+    decorator! {
+      Plain,
+      before: |_, _, _, _| {},
+      after: |_, _, result| result,
+    }
+
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .with(predicate::eq(2), predicate::eq(3))
+      .times(1)
+      .return_const(5);
+    mock_i32_calculator
+      .expect_checked_divide()
+      .with(predicate::eq(1), predicate::eq(0))
+      .times(1)
+      .return_const(Err(CalculatorError::DivisionByZero { dividend: 1 }));
+
+    let recording = Recording {
+      i32_calculator: mock_i32_calculator,
+      events: RefCell::new(Vec::new()),
+    };
+
+    assert_eq!(recording.add(2, 3), 5);
+    assert_eq!(
+      recording.checked_divide(1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+    // `after` isn't called when the checked method fails
+    assert_eq!(
+      recording.events.take(),
+      vec!["Add(2, 3)", "Add = 5", "Divide(1, 0)"]
+    );
+
+    let scaling = Scaling {
+      i32_calculator: IdentityCalculator,
+      calls: Cell::new(0),
+      factor: 10,
+    };
+
+    assert_eq!(scaling.add(2, 3), 50);
+    assert_eq!(scaling.checked_multiply(2, 3), Ok(60));
+    assert_eq!(scaling.calls.get(), 2);
+    assert_eq!(scaling.backend_name(), "identity");
+
+    let plain = Plain {
+      i32_calculator: IdentityCalculator,
+    };
+
+    assert_eq!(plain.subtract(2, 3), -1);
+  }
+
+  #[test]
+  fn solve_2x2_uses_cramers_rule() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    // 2x + y = 5, x - y = 1
+    assert_eq!(app.solve_2x2(2, 1, 1, -1, 5, 1), Some((2, 1)));
+    // x + 2y = 3, 2x + 4y = 6
+    assert_eq!(app.solve_2x2(1, 2, 2, 4, 3, 6), None);
+    // 2x = 1, y = 1
+    assert_eq!(app.solve_2x2(2, 0, 0, 1, 1, 1), None);
+    // 65536x + y = 1, -x + 65536y = 0 has no integral solution, but the determinant 2^32 + 1
+    // wraps to 1, which would give (65536, 1)
+    assert_eq!(app.solve_2x2(65536, 1, -1, 65536, 1, 0), None);
+  }
+
+  #[test]
+  fn saturation_tracking_calculator_counts_saturated_results() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_add()
+      .times(2)
+      .returning(i32::saturating_add);
+    mock_i32_calculator
+      .expect_multiply()
+      .times(1)
+      .returning(i32::saturating_mul);
+
+    let calculator = SaturationTrackingCalculator::new(mock_i32_calculator);
+
+    assert_eq!(calculator.add(i32::MAX, 1), i32::MAX);
+    assert_eq!(calculator.add(1, 2), 3);
+    assert_eq!(calculator.multiply(i32::MIN, 2), i32::MIN);
+    assert_eq!(calculator.saturation_count(), 2);
+  }
+
+  #[test]
+  fn plan_predicts_each_step() {
+    let app = Application::new(Box::new(IdentityCalculator));
+    let plan = app.plan(
+      5,
+      &[
+        Operation::Add(0, 2),
+        Operation::Multiply(0, 3),
+        Operation::Subtract(0, 1),
+      ],
+    );
+
+    assert_eq!(
+      plan,
+      vec![
+        PlanStep {
+          index: 0,
+          operation: Operation::Add(5, 2),
+          predicted: Some(7),
+        },
+        PlanStep {
+          index: 1,
+          operation: Operation::Multiply(7, 3),
+          predicted: Some(21),
+        },
+        PlanStep {
+          index: 2,
+          operation: Operation::Subtract(21, 1),
+          predicted: Some(20),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn recording_calculator_indexes_cool_algorithm_operations() {
+    let app = Application::new(Box::new(RecordingCalculator::new(IdentityCalculator)));
+
+    assert_eq!(app.cool_algorithm(7), 7);
+
+    assert_eq!(app.i32_calculator.len(), 4);
+    assert!(!app.i32_calculator.is_empty());
+    assert_eq!(app.i32_calculator.log()[2], (Operation::Multiply(7, 1), 7));
+
+    assert_eq!(app.i32_calculator.divmod(7, 3), Ok((2, 1)));
+    assert_eq!(app.backend(), "identity");
+    assert_eq!(app.i32_calculator.len(), 4);
+  }
+
+  #[test]
+  fn cross_validate_reports_division_precision_gap() {
+    struct ExactF64Calculator;
+
+    impl F64Calculator for ExactF64Calculator {
+      fn add(&self, x: f64, y: f64) -> f64 {
+        x + y
+      }
+
+      fn subtract(&self, x: f64, y: f64) -> f64 {
+        x - y
+      }
+
+      fn multiply(&self, x: f64, y: f64) -> f64 {
+        x * y
+      }
+
+      fn divide(&self, x: f64, y: f64) -> f64 {
+        x / y
+      }
+    }
+
+    let reports = cross_validate(&IdentityCalculator, &ExactF64Calculator, &[(7, 2), (-9, 4)]);
+    let differences = |kind| {
+      reports
+        .iter()
+        .filter(|report| report.operation.kind() == kind)
+        .map(|report| report.difference)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(reports.len(), 8);
+    assert_eq!(differences(OpKind::Add), [0, 0]);
+    // 7 / 2 truncates to 3 but 3.5 rounds to 4; -9 / 4 gives -2 either way
+    assert_eq!(differences(OpKind::Divide), [1, 0]);
+    assert_eq!(reports[3].operation, Operation::Divide(7, 2));
+    assert_eq!(reports[3].int_result, 3);
+    assert_eq!(reports[3].float_result, 3.5);
+  }
+
+  #[test]
+  fn sum_i64_exceeds_i32_max_without_overflow() {
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    let total = app.sum_i64(&[i32::MAX, i32::MAX, 2, i32::MIN]);
+
+    assert_eq!(total, i64::from(i32::MAX) + 1);
+    assert_eq!(app.sum_i64(&[i32::MAX; 3]), 3 * i64::from(i32::MAX));
+    assert_eq!(app.sum_i64(&[]), 0);
+  }
+
+  #[test]
+  fn ewma_smooths_through_calculator() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    let mut sequence = mockall::Sequence::new();
+    mock_i32_calculator
+      .expect_checked_subtract()
+      .with(predicate::eq(2), predicate::eq(1))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x - y));
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .with(predicate::eq(1), predicate::eq(20))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x * y));
+    mock_i32_calculator
+      .expect_checked_multiply()
+      .with(predicate::eq(1), predicate::eq(10))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x * y));
+    mock_i32_calculator
+      .expect_checked_add()
+      .with(predicate::eq(20), predicate::eq(10))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x + y));
+    mock_i32_calculator
+      .expect_checked_divide()
+      .with(predicate::eq(30), predicate::eq(2))
+      .times(1)
+      .in_sequence(&mut sequence)
+      .returning(|x, y| Ok(x / y));
+
+    let app = Application::new(Box::new(mock_i32_calculator));
+
+    assert_eq!(app.ewma(&[10, 20], 1, 2), Ok(15));
+
+    let app = Application::new(Box::new(IdentityCalculator));
+
+    // 10, then (3 * 30 + 10) / 4 = 25, then (3 * 0 + 25) / 4 = 6, then (3 * 40 + 6) / 4 = 31
+    assert_eq!(app.ewma(&[10, 30, 0, 40], 3, 4), Ok(31));
+    assert_eq!(app.ewma(&[], 1, 2), Ok(0));
+    assert_eq!(
+      app.ewma(&[10, 20], 1, 0),
+      Err(CalculatorError::DivisionByZero { dividend: 1 })
+    );
+  }
+
+  #[test]
+  fn rc_calculator_forwards_every_method() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_add()
+      .times(1)
+      .with(predicate::eq(1), predicate::eq(2))
+      .return_const(Err(CalculatorError::Transient));
+    mock_i32_calculator
+      .expect_validate()
+      .times(1)
+      .with(predicate::eq(3), predicate::eq(4))
+      .return_const(Err(CalculatorError::InvalidOperands { x: 3, y: 4 }));
+    mock_i32_calculator
+      .expect_multiply_add()
+      .times(1)
+      .with(predicate::eq(2), predicate::eq(3), predicate::eq(4))
+      .return_const(Ok(10));
+
+    let calculator = std::rc::Rc::new(mock_i32_calculator);
+    // through the `Rc` impl rather than auto-deref to the mock
+    let calculator: &dyn I32Calculator = &calculator;
+
+    assert_eq!(
+      calculator.checked_add(1, 2),
+      Err(CalculatorError::Transient)
+    );
+    assert_eq!(
+      calculator.validate(3, 4),
+      Err(CalculatorError::InvalidOperands { x: 3, y: 4 })
+    );
+    assert_eq!(calculator.multiply_add(2, 3, 4), Ok(10));
+  }
+
+  #[test]
+  fn shared_mut_calculator_forwards_checked_methods() {
+    let mut mock_i32_calculator = MockI32Calculator::new();
+
+    mock_i32_calculator
+      .expect_checked_divide()
+      .times(1)
+      .with(predicate::eq(6), predicate::eq(3))
+      .return_const(Err(CalculatorError::Transient));
+    mock_i32_calculator
+      .expect_supports()
+      .times(1)
+      .with(predicate::eq(OpKind::Divide))
+      .return_const(false);
+
+    let calculator = SharedMutCalculator::new(mock_i32_calculator);
+
+    assert_eq!(
+      calculator.checked_divide(6, 3),
+      Err(CalculatorError::Transient)
+    );
+    assert!(!calculator.supports(OpKind::Divide));
+  }
+
+  #[test]
+  fn op_kind_index_is_distinct_and_below_count() {
+    let kinds = [
+      OpKind::Add,
+      OpKind::Subtract,
+      OpKind::Multiply,
+      OpKind::Divide,
+    ];
+
+    assert_eq!(kinds.len(), OpKind::COUNT);
+    for (i, kind) in kinds.into_iter().enumerate() {
+      assert_eq!(kind.index(), i);
+    }
+  }
+
+  // only compiled, with `cargo clippy --target wasm32-unknown-unknown --all-targets`, so that the
+  // build fails if an API stops being available on wasm32; running it needs a wasm test runner
+  #[cfg(target_arch = "wasm32")]
+  #[test]
+  fn portable_api_is_available_on_wasm() {
+    let calculator = IdentityCalculator;
+
+    assert_eq!(
+      calculator.checked_add(fold_ops(&calculator, 0, &[Operation::Add(0, 1)]), 1),
+      Ok(2)
+    );
+
+    {
+      let deadline = Instant::now() + core::time::Duration::from_secs(60);
+      let calculator = TimingCalculator::new(DeadlineCalculator::new(IdentityCalculator, deadline));
+
+      assert_eq!(Application::new(Box::new(calculator)).cool_algorithm(1), 1);
+    }
   }
 }
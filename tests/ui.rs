@@ -0,0 +1,13 @@
+#[test]
+fn ui() {
+  let t = trybuild::TestCases::new();
+  // also run with `--no-default-features` to check that the core builds without `std`
+  t.pass("tests/ui/core_api.rs");
+  #[cfg(feature = "alloc")]
+  {
+    t.pass("tests/ui/generic_application.rs");
+    t.compile_fail("tests/ui/application_fields_are_private.rs");
+  }
+  #[cfg(not(feature = "alloc"))]
+  t.compile_fail("tests/ui/application_requires_alloc.rs");
+}
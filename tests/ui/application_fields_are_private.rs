@@ -0,0 +1,7 @@
+use rust_mock::{Application, IdentityCalculator};
+
+fn main() {
+  // `overflow_guard` is only set up by `Application::new` and its builders
+  let app = Application::new(Box::new(IdentityCalculator));
+  let _ = app.overflow_guard;
+}
@@ -0,0 +1,5 @@
+use rust_mock::{Application, IdentityCalculator};
+
+fn main() {
+  let _app = Application::new(IdentityCalculator);
+}
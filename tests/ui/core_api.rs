@@ -0,0 +1,10 @@
+// only uses the API that is available without the `std` and `alloc` features
+use rust_mock::{fold_ops, I32Calculator, IdentityCalculator, Operation};
+
+fn main() {
+  let calculator = IdentityCalculator;
+  let ops = [Operation::Add(0, 2), Operation::Multiply(0, 3)];
+
+  assert_eq!(fold_ops(&calculator, 1, &ops), 9);
+  assert!(calculator.checked_add(i32::MAX, 1).is_err());
+}
@@ -0,0 +1,11 @@
+use rust_mock::{Application, IdentityCalculator, I32Calculator};
+
+fn main() {
+  // statically dispatched
+  let app: Application<IdentityCalculator> = Application::new(Box::new(IdentityCalculator));
+  assert_eq!(app.cool_algorithm(7), 7);
+
+  // dynamically dispatched, the default
+  let app: Application = Application::new(Box::new(IdentityCalculator) as Box<dyn I32Calculator>);
+  assert_eq!(app.cool_algorithm(7), 7);
+}